@@ -3,6 +3,9 @@ use std::io::{self, Read};
 use clap::{CommandFactory, FromArgMatches, Parser};
 
 use coman::cli::commands::Commands;
+use coman::cli::format::OutputFormat;
+use coman::cli::output::FailedStatus;
+use coman::core::config::{ColorMode, Config};
 use coman::helper;
 
 #[derive(Parser)]
@@ -10,6 +13,15 @@ use coman::helper;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Output format: text, or json and ndjson for scripts"
+    )]
+    format: OutputFormat,
 }
 
 #[tokio::main]
@@ -19,6 +31,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         io::stdin().read_to_end(&mut stdin_input)?;
     }
 
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Ignoring the config file: {}", e);
+        Config::default()
+    });
+    // COMAN_JSON wins over the configured data file
+    if let Some(data_file) = config.data_file_path() {
+        helper::set_data_file(data_file);
+    }
+    match config.color {
+        Some(ColorMode::Always) => colored::control::set_override(true),
+        Some(ColorMode::Never) => colored::control::set_override(false),
+        Some(ColorMode::Auto) | None => {}
+    }
+    config.install();
+
     let file_path = helper::get_file_path();
 
     let version: &'static str = Box::leak(
@@ -34,14 +61,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let cli = Cli::from_arg_matches(&args)?;
 
-    let result = cli.command.run(stdin_input).await;
+    if !cli.format.is_text() {
+        colored::control::set_override(false);
+    }
+
+    let result = cli.command.run(stdin_input, cli.format).await;
 
-    match result {
-        Ok(_s) => {}
-        Err(e) => {
+    if let Err(e) = result {
+        // --fail: the output was printed, exit with the status class
+        let failed = e.downcast_ref::<FailedStatus>();
+        if !cli.format.is_text() {
+            eprintln!("{}", serde_json::json!({ "error": e.to_string() }));
+        } else if failed.is_some() {
+            eprintln!("{}", e);
+        } else {
             eprintln!("Failed to run command : {} \n {}", cli.command, e);
-            std::process::exit(1);
         }
+        std::process::exit(failed.map_or(1, FailedStatus::exit_code));
     }
     Ok(())
 }
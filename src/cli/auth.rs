@@ -0,0 +1,51 @@
+//! CLI commands for logging in to OAuth 2.0 protected collections
+
+use clap::Subcommand;
+use std::fmt;
+
+#[derive(Clone, Subcommand)]
+pub enum AuthCommands {
+    #[clap(about = "Log in with the OAuth2 authorization code flow and store the tokens")]
+    Login {
+        collection: String,
+
+        #[clap(
+            short = 'e',
+            long,
+            default_value = "",
+            required = false,
+            help = "Use the auth of an endpoint instead of the collection"
+        )]
+        endpoint: String,
+    },
+    #[clap(about = "Remove the stored OAuth2 tokens of a collection or endpoint")]
+    Logout {
+        collection: String,
+
+        #[clap(short = 'e', long, default_value = "", required = false)]
+        endpoint: String,
+    },
+}
+
+impl fmt::Display for AuthCommands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthCommands::Login {
+                collection,
+                endpoint,
+            } => write!(
+                f,
+                "Login Command: collection: '{}', endpoint: '{}'",
+                collection, endpoint
+            ),
+            AuthCommands::Logout {
+                collection,
+                endpoint,
+            } => write!(
+                f,
+                "Logout Command: collection: '{}', endpoint: '{}'",
+                collection, endpoint
+            ),
+        }
+    }
+}
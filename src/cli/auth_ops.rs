@@ -0,0 +1,81 @@
+use colored::Colorize;
+use reqwest::Client;
+
+use crate::cli::{
+    auth::AuthCommands, manager::ManagerCommands, request::RequestCommands,
+    request_data::RequestData,
+};
+use crate::models::collection::{Auth, OAuth2};
+
+impl AuthCommands {
+    /// Get the OAuth2 settings of a collection or endpoint, with their templates resolved
+    async fn get_oauth2(
+        collection: &str,
+        endpoint: &str,
+    ) -> Result<OAuth2, Box<dyn std::error::Error>> {
+        let manager = ManagerCommands::get_manager();
+        let col = manager
+            .get_collection(collection)
+            .await?
+            .ok_or("Collection not found")?;
+        let auth = if endpoint.is_empty() {
+            col.auth.clone()
+        } else {
+            let req = col.get_request(endpoint).ok_or("Endpoint not found")?;
+            col.request_auth(&req)
+        };
+        if !matches!(auth, Some(Auth::OAuth2(_))) {
+            return Err(format!("'{}' does not use OAuth2 auth", collection).into());
+        }
+
+        let mut data = RequestData {
+            auth,
+            endpoint: Some(format!("{}/{}", collection, endpoint)),
+            ..Default::default()
+        };
+        RequestCommands::fill_request_placeholders(&mut data, &mut String::new(), true)?;
+        RequestCommands::render_request_generators(&mut data, &mut String::new())?;
+        match data.auth {
+            Some(Auth::OAuth2(oauth)) => Ok(oauth),
+            _ => Err("OAuth2 auth not found".into()),
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Login {
+                collection,
+                endpoint,
+            } => {
+                let oauth = Self::get_oauth2(collection, endpoint).await?;
+                let login = oauth.authorization_request()?;
+
+                println!("Open this URL in your browser to log in:\n");
+                println!("{}\n", login.url.bright_cyan());
+                println!("Waiting for the redirect to {} ...", login.redirect_uri);
+
+                let token = oauth.complete_login(&Client::new(), login).await?;
+                println!(
+                    "Logged in{}",
+                    if token.refresh_token.is_some() {
+                        " (with a refresh token)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            Self::Logout {
+                collection,
+                endpoint,
+            } => {
+                let oauth = Self::get_oauth2(collection, endpoint).await?;
+                if oauth.remove_token()? {
+                    println!("Logged out");
+                } else {
+                    println!("No stored tokens");
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+//! CLI commands for reading and changing the user configuration file
+
+use clap::Subcommand;
+use std::fmt;
+
+#[derive(Clone, Subcommand)]
+pub enum ConfigCommands {
+    #[clap(about = "Print the value of a config key")]
+    Get { key: String },
+    #[clap(about = "Set a config key")]
+    Set { key: String, value: String },
+    #[clap(about = "Remove a config key, going back to the built-in default")]
+    Unset { key: String },
+    #[clap(about = "List the config keys and their values")]
+    List,
+}
+
+impl fmt::Display for ConfigCommands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigCommands::Get { key } => write!(f, "Get Command: key: '{}'", key),
+            ConfigCommands::Set { key, value } => {
+                write!(f, "Set Command: key: '{}', value: '{}'", key, value)
+            }
+            ConfigCommands::Unset { key } => write!(f, "Unset Command: key: '{}'", key),
+            ConfigCommands::List => write!(f, "List Command"),
+        }
+    }
+}
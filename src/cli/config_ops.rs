@@ -0,0 +1,40 @@
+use colored::Colorize;
+
+use crate::cli::config::ConfigCommands;
+use crate::core::config::{Config, CONFIG_KEYS};
+
+impl ConfigCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Get { key } => {
+                let value = Config::load()?
+                    .get(key)?
+                    .ok_or_else(|| format!("Config key '{}' is not set", key))?;
+                println!("{}", value);
+            }
+            Self::Set { key, value } => {
+                let mut config = Config::load()?;
+                config.set(key, value)?;
+                config.save()?;
+                println!("{} = {}", key.bright_yellow(), value);
+            }
+            Self::Unset { key } => {
+                let mut config = Config::load()?;
+                config.unset(key)?;
+                config.save()?;
+                println!("Unset {}", key.bright_yellow());
+            }
+            Self::List => {
+                let config = Config::load()?;
+                println!("Config file: {}", Config::path().display());
+                for (key, description) in CONFIG_KEYS {
+                    match config.get(key)? {
+                        Some(value) => println!("{} = {}", key.bright_yellow(), value),
+                        None => println!("{} - {}", key, description.dimmed()),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+//! CLI commands for inspecting and clearing collection cookie jars
+
+use clap::Subcommand;
+use std::fmt;
+
+#[derive(Clone, Subcommand)]
+pub enum CookiesCommands {
+    #[clap(about = "List the cookies stored for a collection")]
+    List { collection: String },
+    #[clap(about = "Remove the cookies stored for a collection")]
+    Clear { collection: String },
+}
+
+impl fmt::Display for CookiesCommands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookiesCommands::List { collection } => {
+                write!(f, "List Command: collection: '{}'", collection)
+            }
+            CookiesCommands::Clear { collection } => {
+                write!(f, "Clear Command: collection: '{}'", collection)
+            }
+        }
+    }
+}
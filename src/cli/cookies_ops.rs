@@ -0,0 +1,75 @@
+use colored::Colorize;
+use cookie_store::{CookieDomain, CookieExpiration};
+
+use crate::cli::{cookies::CookiesCommands, manager::ManagerCommands};
+use crate::core::cookies::CookieJar;
+use crate::core::utils::utc_date_time;
+
+impl CookiesCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::List { collection } => {
+                let col = ManagerCommands::get_manager()
+                    .get_collection(collection)
+                    .await?
+                    .ok_or("Collection not found")?;
+                if !col.cookies {
+                    println!(
+                        "{}",
+                        format!(
+                            "Cookie jar is off for '{}', enable it with: coman man update {} --cookies true",
+                            collection, collection
+                        )
+                        .yellow()
+                    );
+                }
+
+                let cookies = CookieJar::load(collection).list();
+                if cookies.is_empty() {
+                    println!("No cookies");
+                }
+                for cookie in cookies {
+                    let domain = match &cookie.domain {
+                        CookieDomain::Suffix(domain) => format!(".{}", domain),
+                        domain => String::from(domain),
+                    };
+                    let expires = match cookie.expires {
+                        CookieExpiration::AtUtc(time) => {
+                            let (year, month, day, hour, minute, second) =
+                                utc_date_time(time.unix_timestamp().max(0) as u64);
+                            format!(
+                                "expires {:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+                                year, month, day, hour, minute, second
+                            )
+                        }
+                        CookieExpiration::SessionEnd => "session".to_string(),
+                    };
+                    let mut flags = String::new();
+                    if cookie.secure() == Some(true) {
+                        flags.push_str(" Secure");
+                    }
+                    if cookie.http_only() == Some(true) {
+                        flags.push_str(" HttpOnly");
+                    }
+                    println!(
+                        "{}={} - {}{} - {}{}",
+                        cookie.name().bright_yellow(),
+                        cookie.value(),
+                        domain.bright_white(),
+                        &*cookie.path,
+                        expires,
+                        flags.bright_cyan()
+                    );
+                }
+            }
+            Self::Clear { collection } => {
+                if CookieJar::clear(collection)? {
+                    println!("Cookies cleared");
+                } else {
+                    println!("No cookies");
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Machine-readable output of the CLI commands
+
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How commands print their results
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored text for people
+    #[default]
+    Text,
+    /// One JSON document
+    Json,
+    /// One JSON document per line, one per result
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// Whether results are printed as text
+    pub fn is_text(self) -> bool {
+        self == OutputFormat::Text
+    }
+
+    /// Print a value: pretty JSON, or a single line for ndjson
+    pub fn print<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(&mut io::stdout().lock(), value)
+    }
+
+    /// Print results: a JSON array, or one line per result for ndjson
+    pub fn print_all<T: Serialize>(self, values: &[T]) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_all(&mut io::stdout().lock(), values)
+    }
+
+    /// Write a value as [`OutputFormat::print`] prints it
+    pub fn write<T: Serialize + ?Sized>(
+        self,
+        out: &mut impl Write,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            OutputFormat::Ndjson => writeln!(out, "{}", serde_json::to_string(value)?)?,
+            _ => writeln!(out, "{}", serde_json::to_string_pretty(value)?)?,
+        }
+        Ok(())
+    }
+
+    /// Write results as [`OutputFormat::print_all`] prints them
+    pub fn write_all<T: Serialize>(
+        self,
+        out: &mut impl Write,
+        values: &[T],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            OutputFormat::Ndjson => values.iter().try_for_each(|value| self.write(out, value)),
+            _ => self.write(out, values),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_write_all_formats() {
+        let results = [
+            json!({"endpoint": "users", "status": 200}),
+            json!({"endpoint": "orders", "status": 500}),
+        ];
+
+        let mut out = Vec::new();
+        OutputFormat::Ndjson.write_all(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, results);
+        assert!(text.ends_with("}\n"));
+
+        let mut out = Vec::new();
+        OutputFormat::Json.write_all(&mut out, &results).unwrap();
+        let document: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(document, json!(results));
+        assert!(String::from_utf8(out).unwrap().lines().count() > 2);
+    }
+
+    /// Writer refusing every write, like a closed pipe
+    struct Closed;
+
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::BrokenPipe))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_errors() {
+        // JSON object keys must be strings
+        let values = [HashMap::from([((1, 2), "point")])];
+        for format in [OutputFormat::Json, OutputFormat::Ndjson] {
+            let mut out = Vec::new();
+            let error = format.write_all(&mut out, &values).unwrap_err();
+            assert!(
+                error.to_string().contains("key must be a string"),
+                "{}",
+                error
+            );
+            assert!(out.is_empty());
+
+            let error = format.write_all(&mut Closed, &[json!(1)]).unwrap_err();
+            let error = error.downcast_ref::<io::Error>().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        }
+
+        // Nothing is written for no results with ndjson
+        let mut out = Vec::new();
+        OutputFormat::Ndjson
+            .write_all::<Value>(&mut out, &[])
+            .unwrap();
+        assert!(out.is_empty());
+    }
+}
@@ -7,10 +7,15 @@ use clap::Subcommand;
 use std::fmt;
 
 use crate::core::collection_manager::CollectionManager;
-use crate::models::collection::Method;
+use crate::core::utils::join_url;
+use crate::models::collection::{
+    Auth, ConnectConfig, FormPart, Method, RequestSettings, TlsConfig,
+};
 
 use super::request::RequestCommands;
-use super::request_data::RequestData;
+use super::request_data::{
+    ConnectArgs, ProtocolArgs, ProxyArgs, RequestData, RetryArgs, SettingsArgs, TlsArgs,
+};
 
 #[derive(Clone, Subcommand)]
 pub enum ManagerCommands {
@@ -50,6 +55,92 @@ pub enum ManagerCommands {
 
         #[clap(short = 'b', long, default_value = "", required = false)]
         body: String,
+
+        #[clap(
+            short = 'a',
+            long = "auth",
+            value_parser = RequestData::parse_auth,
+            value_name = "AUTH",
+            help = "Auth: none, basic:USER:PASSWORD, bearer:TOKEN, apikey:[header|query:]NAME:VALUE, oauth2:OPTIONS, sigv4:ACCESS_KEY:SECRET_KEY:REGION:SERVICE[:SESSION_TOKEN], hmac:OPTIONS, jwt:OPTIONS or digest:USER:PASSWORD",
+            required = false
+        )]
+        auth: Option<Auth>,
+
+        #[clap(
+            long,
+            value_name = "BOOL",
+            help = "Enable or disable the collection cookie jar",
+            required = false
+        )]
+        cookies: Option<bool>,
+
+        #[clap(flatten)]
+        tls: TlsArgs,
+
+        #[clap(
+            long = "no-tls",
+            default_value = "false",
+            help = "Remove the collection TLS settings"
+        )]
+        no_tls: bool,
+
+        #[clap(flatten)]
+        proxy: ProxyArgs,
+
+        #[clap(
+            long = "clear-proxy",
+            default_value = "false",
+            help = "Remove the collection proxy"
+        )]
+        clear_proxy: bool,
+
+        #[clap(
+            long = "unix-socket",
+            value_name = "PATH",
+            help = "Reach the collection over a Unix domain socket, or an empty path to stop",
+            required = false
+        )]
+        unix_socket: Option<String>,
+
+        #[clap(flatten)]
+        connect: ConnectArgs,
+
+        #[clap(
+            long = "clear-connect",
+            default_value = "false",
+            help = "Remove the collection connection settings"
+        )]
+        clear_connect: bool,
+
+        #[clap(flatten)]
+        protocol: ProtocolArgs,
+
+        #[clap(
+            long = "clear-protocol",
+            default_value = "false",
+            help = "Negotiate the HTTP version again instead of the collection one"
+        )]
+        clear_protocol: bool,
+
+        #[clap(flatten)]
+        retry: RetryArgs,
+
+        #[clap(
+            long = "clear-retry",
+            default_value = "false",
+            help = "Remove the endpoint retry policy"
+        )]
+        clear_retry: bool,
+
+        #[clap(flatten)]
+        settings: SettingsArgs,
+
+        #[clap(
+            long = "clear-settings",
+            default_value = "false",
+            help = "Remove the collection or endpoint timeout and redirect settings"
+        )]
+        clear_settings: bool,
     },
     #[clap(about = "Delete a collection or endpoint")]
     Delete {
@@ -87,6 +178,56 @@ pub enum ManagerCommands {
             required = false
         )]
         headers: Vec<(String, String)>,
+
+        #[clap(
+            short = 'q',
+            long = "query",
+            value_parser = RequestData::parse_key_value,
+            value_name = "KEY=VALUE",
+            num_args = 1..,
+            required = false
+        )]
+        query: Vec<(String, String)>,
+
+        #[clap(
+            short = 'a',
+            long = "auth",
+            value_parser = RequestData::parse_auth,
+            value_name = "AUTH",
+            help = "Auth: none, basic:USER:PASSWORD, bearer:TOKEN, apikey:[header|query:]NAME:VALUE, oauth2:OPTIONS, sigv4:ACCESS_KEY:SECRET_KEY:REGION:SERVICE[:SESSION_TOKEN], hmac:OPTIONS, jwt:OPTIONS or digest:USER:PASSWORD",
+            required = false
+        )]
+        auth: Option<Auth>,
+
+        #[clap(
+            long,
+            default_value = "false",
+            help = "Keep a cookie jar for the collection, persisted between runs"
+        )]
+        cookies: bool,
+
+        #[clap(flatten)]
+        tls: TlsArgs,
+
+        #[clap(flatten)]
+        proxy: ProxyArgs,
+
+        #[clap(
+            long = "unix-socket",
+            value_name = "PATH",
+            help = "Reach the collection over a Unix domain socket",
+            required = false
+        )]
+        unix_socket: Option<String>,
+
+        #[clap(flatten)]
+        connect: ConnectArgs,
+
+        #[clap(flatten)]
+        protocol: ProtocolArgs,
+
+        #[clap(flatten)]
+        settings: SettingsArgs,
     },
     #[clap(about = "Add a new endpoint to a collection")]
     Endpoint {
@@ -109,6 +250,63 @@ pub enum ManagerCommands {
 
         #[clap(short = 'b', long, default_value = "", required = false)]
         body: String,
+
+        #[clap(
+            short = 'F',
+            long = "form",
+            value_parser = RequestData::parse_form_part,
+            value_name = "NAME=VALUE|NAME=@PATH[;type=MIME][;filename=NAME]",
+            num_args = 1..,
+            required = false
+        )]
+        form: Vec<FormPart>,
+
+        #[clap(
+            short = 'q',
+            long = "query",
+            value_parser = RequestData::parse_key_value,
+            value_name = "KEY=VALUE",
+            num_args = 1..,
+            required = false
+        )]
+        query: Vec<(String, String)>,
+
+        #[clap(
+            short = 'p',
+            long = "param",
+            value_parser = RequestData::parse_key_value,
+            value_name = "NAME=VALUE",
+            num_args = 1..,
+            required = false,
+            help = "Default value for a {name} path parameter"
+        )]
+        params: Vec<(String, String)>,
+
+        #[clap(
+            short = 'U',
+            long = "urlencoded",
+            value_parser = RequestData::parse_key_value,
+            value_name = "KEY=VALUE",
+            num_args = 1..,
+            required = false
+        )]
+        urlencoded: Vec<(String, String)>,
+
+        #[clap(
+            short = 'a',
+            long = "auth",
+            value_parser = RequestData::parse_auth,
+            value_name = "AUTH",
+            help = "Auth: none, basic:USER:PASSWORD, bearer:TOKEN, apikey:[header|query:]NAME:VALUE, oauth2:OPTIONS, sigv4:ACCESS_KEY:SECRET_KEY:REGION:SERVICE[:SESSION_TOKEN], hmac:OPTIONS, jwt:OPTIONS or digest:USER:PASSWORD",
+            required = false
+        )]
+        auth: Option<Auth>,
+
+        #[clap(flatten)]
+        retry: RetryArgs,
+
+        #[clap(flatten)]
+        settings: SettingsArgs,
     },
 }
 
@@ -131,11 +329,44 @@ impl fmt::Display for ManagerCommands {
                 url: _,
                 headers,
                 body,
+                auth,
+                cookies,
+                tls,
+                no_tls,
+                proxy,
+                clear_proxy,
+                unix_socket,
+                connect,
+                clear_connect,
+                protocol,
+                clear_protocol,
+                retry,
+                clear_retry,
+                settings,
+                clear_settings,
             } => {
                 write!(
                     f,
-                    "Update Command: collection: '{}', endpoint: '{}', headers: {:?}, body: '{}'",
-                    collection, endpoint, headers, body
+                    "Update Command: collection: '{}', endpoint: '{}', headers: {:?}, body: '{}', auth: {:?}, cookies: {:?}, tls: '{}', no_tls: {}, proxy: {:?}, clear_proxy: {}, unix_socket: {:?}, connect: '{}', clear_connect: {}, protocol: {:?}, clear_protocol: {}, retry: {:?}, clear_retry: {}, settings: '{}', clear_settings: {}",
+                    collection,
+                    endpoint,
+                    headers,
+                    body,
+                    auth.as_ref().map(Auth::redacted),
+                    cookies,
+                    TlsConfig::from(tls),
+                    no_tls,
+                    proxy.config().ok().flatten().map(|proxy| proxy.to_string()),
+                    clear_proxy,
+                    unix_socket,
+                    ConnectConfig::from(connect),
+                    clear_connect,
+                    protocol.protocol(),
+                    clear_protocol,
+                    retry.policy().ok().flatten().map(|policy| policy.to_string()),
+                    clear_retry,
+                    RequestSettings::from(settings),
+                    clear_settings
                 )
             }
             ManagerCommands::Delete {
@@ -161,11 +392,35 @@ impl fmt::Display for ManagerCommands {
                     collection, endpoint, to_col, new_name
                 )
             }
-            ManagerCommands::Col { name, url, headers } => {
+            ManagerCommands::Col {
+                name,
+                url,
+                headers,
+                query,
+                auth,
+                cookies,
+                tls,
+                proxy,
+                unix_socket,
+                connect,
+                protocol,
+                settings,
+            } => {
                 write!(
                     f,
-                    "Col Command: name: '{}', url: '{}', headers: {:?}",
-                    name, url, headers
+                    "Col Command: name: '{}', url: '{}', headers: {:?}, query: {:?}, auth: {:?}, cookies: {}, tls: '{}', proxy: {:?}, unix_socket: {:?}, connect: '{}', protocol: {:?}, settings: '{}'",
+                    name,
+                    url,
+                    headers,
+                    query,
+                    auth.as_ref().map(Auth::redacted),
+                    cookies,
+                    TlsConfig::from(tls),
+                    proxy.config().ok().flatten().map(|proxy| proxy.to_string()),
+                    unix_socket,
+                    ConnectConfig::from(connect),
+                    protocol.protocol(),
+                    RequestSettings::from(settings)
                 )
             }
             ManagerCommands::Endpoint {
@@ -175,9 +430,16 @@ impl fmt::Display for ManagerCommands {
                 method,
                 headers,
                 body,
+                form,
+                query,
+                params,
+                urlencoded,
+                auth,
+                retry,
+                settings,
             } => {
-                write!(f, "Endpoint Command: collection: '{}', name: '{}', path: '{}', method: '{}', headers: {:?}, body: '{}'",
-                    collection, name, path, method, headers, body)
+                write!(f, "Endpoint Command: collection: '{}', name: '{}', path: '{}', method: '{}', headers: {:?}, body: '{}', form: {:?}, query: {:?}, params: {:?}, urlencoded: {:?}, auth: {:?}, retry: {:?}, settings: '{}'",
+                    collection, name, path, method, headers, body, form, query, params, urlencoded, auth.as_ref().map(Auth::redacted), retry.policy().ok().flatten().map(|policy| policy.to_string()), RequestSettings::from(settings))
             }
         }
     }
@@ -195,9 +457,29 @@ impl ManagerCommands {
         let col = manager.get_collection(col_name).await.ok()??;
         let req = col.get_request(ep_name)?;
         let data = RequestData {
-            url: format!("{}{}", col.url, req.endpoint),
+            url: join_url(&col.url, &req.endpoint),
             headers: manager.get_endpoint_headers(col_name, ep_name).await,
             body: req.body.clone().unwrap_or_default(),
+            form: req.multipart.clone().unwrap_or_default(),
+            query: manager.get_endpoint_query(col_name, ep_name).await.ok()?,
+            params: req.params.clone(),
+            urlencoded: req.urlencoded.clone().unwrap_or_default(),
+            auth: col.request_auth(&req),
+            unix_socket: col.socket.clone(),
+            endpoint: Some(format!("{}/{}", col_name, ep_name)),
+            tls: col.tls.clone().unwrap_or_default().into(),
+            proxy: col.proxy.clone().map(ProxyArgs::from).unwrap_or_default(),
+            connect: col.connect.clone().unwrap_or_default().into(),
+            protocol: col.protocol.into(),
+            retry: req.retry.clone().into(),
+            settings: col
+                .settings
+                .clone()
+                .unwrap_or_default()
+                .merge(&req.settings.clone().unwrap_or_default())
+                .into(),
+            trace: None,
+            cookie_jar: col.cookies.then(|| col.name.clone()),
         };
 
         Some(match req.method {
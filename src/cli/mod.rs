@@ -3,9 +3,18 @@
 //! This module provides the CLI commands for managing collections and
 //! making HTTP requests from the command line.
 
+pub mod auth;
+pub mod auth_ops;
 pub mod commands;
+pub mod config;
+pub mod config_ops;
+pub mod cookies;
+pub mod cookies_ops;
+pub mod format;
 pub mod manager;
 pub mod manager_ops;
+pub mod output;
+pub mod prompt;
 pub mod request;
 pub mod request_data;
 pub mod request_ops;
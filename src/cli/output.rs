@@ -0,0 +1,415 @@
+//! Output selectors of `req` and `run` for shell pipelines: `--only`,
+//! `--write-out` and `--fail`
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::Args;
+
+use crate::cli::format::OutputFormat;
+use crate::HttpResponse;
+
+/// Variables of `--write-out`
+const WRITE_OUT_VARIABLES: &[&str] = &[
+    "status",
+    "http_code",
+    "http_version",
+    "url_effective",
+    "content_type",
+    "size_download",
+    "num_redirects",
+    "num_retries",
+    "time_namelookup",
+    "time_connect",
+    "time_starttransfer",
+    "time_total",
+];
+
+/// Output options of a request or a run
+#[derive(Args, Clone, Debug, Default)]
+pub struct OutputArgs {
+    #[clap(
+        short = 'f',
+        long = "fail",
+        default_value = "false",
+        help = "Exit with 4 on a 4xx status and 5 on a 5xx status"
+    )]
+    pub fail: bool,
+
+    #[clap(
+        long = "only",
+        value_name = "PART",
+        help = "Print only part of the response: status, headers, header:NAME or body",
+        required = false
+    )]
+    pub only: Option<OutputPart>,
+
+    #[clap(
+        short = 'w',
+        long = "write-out",
+        value_name = "FORMAT",
+        help = "Print FORMAT after the response, with variables like %{status} and %{time_total}",
+        required = false
+    )]
+    pub write_out: Option<WriteOut>,
+}
+
+/// Part of a response printed by `--only`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputPart {
+    Status,
+    Headers,
+    /// Values of a header, one per line
+    Header(String),
+    /// Body as received, without coloring or pretty-printing
+    Body,
+}
+
+/// `--write-out` format: text with `%{variable}` placeholders
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteOut {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Variable(String),
+}
+
+/// Status refused by `--fail`
+#[derive(Debug)]
+pub struct FailedStatus {
+    pub status: u16,
+    pub status_text: String,
+}
+
+impl OutputArgs {
+    /// Check the options go with the output format and streaming
+    pub fn check(&self, format: OutputFormat, stream: bool) -> Result<(), String> {
+        if !format.is_text() && (self.only.is_some() || self.write_out.is_some()) {
+            return Err("--only and --write-out cannot be used with --format".to_string());
+        }
+        if stream && self.only.is_some() {
+            return Err("--only cannot be used with --stream".to_string());
+        }
+        Ok(())
+    }
+
+    /// Print the `--write-out` text, then fail on error statuses with `--fail`
+    pub fn finish(&self, response: &HttpResponse) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(write_out) = &self.write_out {
+            print!("{}", write_out.render(response));
+            std::io::stdout().flush()?;
+        }
+        if self.fail && response.status >= 400 {
+            return Err(Box::new(FailedStatus {
+                status: response.status,
+                status_text: response.status_text.clone(),
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl OutputPart {
+    /// Print this part of a response
+    pub fn print(&self, response: &HttpResponse) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            OutputPart::Status => println!("{}", response.status),
+            OutputPart::Headers => {
+                for (key, value) in &response.headers {
+                    println!("{}: {}", key, value);
+                }
+            }
+            OutputPart::Header(name) => {
+                let values: Vec<&str> = response
+                    .headers
+                    .iter()
+                    .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.as_str())
+                    .collect();
+                if values.is_empty() {
+                    return Err(format!("Header '{}' not found in the response", name).into());
+                }
+                for value in values {
+                    println!("{}", value);
+                }
+            }
+            OutputPart::Body => {
+                print!("{}", response.body);
+                std::io::stdout().flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OutputPart {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(OutputPart::Status),
+            "headers" => Ok(OutputPart::Headers),
+            "body" => Ok(OutputPart::Body),
+            _ => match s.strip_prefix("header:") {
+                Some(name) if !name.trim().is_empty() => {
+                    Ok(OutputPart::Header(name.trim().to_string()))
+                }
+                _ => Err(format!(
+                    "Invalid part: '{}'. Use status, headers, header:NAME or body",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl WriteOut {
+    /// The text with the variables replaced by the values of a response
+    pub fn render(&self, response: &HttpResponse) -> String {
+        let seconds = |duration: Duration| format!("{:.6}", duration.as_secs_f64());
+        let timings = &response.timings;
+        let lookup = timings.dns.unwrap_or_default();
+        let connect = lookup + timings.connect.unwrap_or_default();
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Variable(name) => match name.as_str() {
+                    "status" | "http_code" => response.status.to_string(),
+                    "http_version" => response.version.to_string(),
+                    "url_effective" => response.url.clone(),
+                    "content_type" => response
+                        .headers
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default(),
+                    "size_download" => response.body.len().to_string(),
+                    "num_redirects" => response.redirects.len().to_string(),
+                    "num_retries" => response.attempts.len().to_string(),
+                    "time_namelookup" => seconds(lookup),
+                    "time_connect" => seconds(connect),
+                    "time_starttransfer" => seconds(connect + timings.ttfb),
+                    _ => seconds(timings.total),
+                },
+            })
+            .collect()
+    }
+}
+
+impl FromStr for WriteOut {
+    type Err = String;
+
+    /// Parse `%{variable}` placeholders, `%%` and the `\n`, `\t`, `\r` and `\\` escapes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('%', Some('%')) => {
+                    chars.next();
+                    text.push('%');
+                }
+                ('%', Some('{')) => {
+                    chars.next();
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("Unclosed --write-out variable '%{{{}'", name));
+                    }
+                    if !WRITE_OUT_VARIABLES.contains(&name.as_str()) {
+                        return Err(format!(
+                            "Unknown --write-out variable '{}'. Use one of: {}",
+                            name,
+                            WRITE_OUT_VARIABLES.join(", ")
+                        ));
+                    }
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Variable(name));
+                }
+                ('\\', Some(&escaped @ ('n' | 't' | 'r' | '\\'))) => {
+                    chars.next();
+                    text.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        _ => '\\',
+                    });
+                }
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(WriteOut {
+            source: s.to_string(),
+            segments,
+        })
+    }
+}
+
+impl fmt::Display for OutputArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fail: {}, only: {:?}, write_out: {:?}",
+            self.fail,
+            self.only.as_ref().map(OutputPart::to_string),
+            self.write_out.as_ref().map(|write_out| &write_out.source)
+        )
+    }
+}
+
+impl fmt::Display for OutputPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputPart::Status => write!(f, "status"),
+            OutputPart::Headers => write!(f, "headers"),
+            OutputPart::Header(name) => write!(f, "header:{}", name),
+            OutputPart::Body => write!(f, "body"),
+        }
+    }
+}
+
+impl FailedStatus {
+    /// Exit code of the status class: 4 for client errors, 5 for server errors
+    pub fn exit_code(&self) -> i32 {
+        (self.status / 100).into()
+    }
+}
+
+impl fmt::Display for FailedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The server answered {}", self.status_text)
+    }
+}
+
+impl std::error::Error for FailedStatus {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HttpVersion, Timings};
+
+    fn response(status: u16, status_text: &str) -> HttpResponse {
+        HttpResponse {
+            version: HttpVersion::Http11,
+            status,
+            status_text: status_text.to_string(),
+            headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+            body: "busy".to_string(),
+            elapsed_ms: 250,
+            timings: Timings {
+                dns: Some(Duration::from_millis(10)),
+                connect: Some(Duration::from_millis(20)),
+                ttfb: Duration::from_millis(200),
+                download: Duration::from_millis(20),
+                total: Duration::from_millis(250),
+            },
+            url: "http://localhost/".to_string(),
+            attempts: Vec::new(),
+            redirects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_write_out() {
+        let response = response(503, "503 Service Unavailable");
+
+        let write_out: WriteOut = "%{status} %{time_total}\\n".parse().unwrap();
+        assert_eq!(write_out.render(&response), "503 0.250000\n");
+        let write_out: WriteOut = "%{content_type}|%{time_connect}|%{time_starttransfer}|100%%"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            write_out.render(&response),
+            "text/plain|0.030000|0.230000|100%"
+        );
+        assert!("%{speed}".parse::<WriteOut>().is_err());
+
+        assert_eq!(
+            "header:ETag".parse::<OutputPart>(),
+            Ok(OutputPart::Header("ETag".to_string()))
+        );
+        assert!("header:".parse::<OutputPart>().is_err());
+
+        let args = OutputArgs {
+            fail: true,
+            ..Default::default()
+        };
+        let error = args.finish(&response).unwrap_err();
+        let failed = error.downcast_ref::<FailedStatus>().unwrap();
+        assert_eq!(failed.exit_code(), 5);
+    }
+
+    #[test]
+    fn test_output_errors() {
+        assert_eq!(
+            "%{status".parse::<WriteOut>().unwrap_err(),
+            "Unclosed --write-out variable '%{status'"
+        );
+        assert!("%{speed}"
+            .parse::<WriteOut>()
+            .unwrap_err()
+            .starts_with("Unknown --write-out variable 'speed'. Use one of: status,"));
+        assert_eq!(
+            "Status".parse::<OutputPart>().unwrap_err(),
+            "Invalid part: 'Status'. Use status, headers, header:NAME or body"
+        );
+        assert!("header: ".parse::<OutputPart>().is_err());
+
+        let only = OutputArgs {
+            only: Some(OutputPart::Body),
+            ..Default::default()
+        };
+        assert_eq!(
+            only.check(OutputFormat::Json, false).unwrap_err(),
+            "--only and --write-out cannot be used with --format"
+        );
+        assert_eq!(
+            only.check(OutputFormat::Text, true).unwrap_err(),
+            "--only cannot be used with --stream"
+        );
+        let write_out = OutputArgs {
+            write_out: Some("%{status}".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(write_out.check(OutputFormat::Ndjson, false).is_err());
+        assert!(write_out.check(OutputFormat::Text, true).is_ok());
+
+        let error = OutputPart::Header("ETag".to_string())
+            .print(&response(200, "200 OK"))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Header 'ETag' not found in the response");
+
+        let fail = OutputArgs {
+            fail: true,
+            ..Default::default()
+        };
+        let error = fail.finish(&response(404, "404 Not Found")).unwrap_err();
+        let failed = error.downcast_ref::<FailedStatus>().unwrap();
+        assert_eq!(failed.exit_code(), 4);
+        assert_eq!(error.to_string(), "The server answered 404 Not Found");
+        assert!(fail.finish(&response(304, "304 Not Modified")).is_ok());
+        assert!(OutputArgs::default()
+            .finish(&response(500, "500 Internal Server Error"))
+            .is_ok());
+    }
+}
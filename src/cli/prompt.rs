@@ -0,0 +1,168 @@
+//! Interactive prompts for missing request data
+//!
+//! Prompts read from the terminal. When stdin is not a TTY they fail fast
+//! with an error instead of blocking on input that will never come.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use colored::Colorize;
+
+use crate::core::template::Placeholder;
+use crate::helper;
+
+/// State file remembering the last values entered per endpoint
+pub static PROMPT_HISTORY_FILE: &str = "prompts.json";
+
+type PromptResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// Check if prompts can be shown (stdin is a terminal)
+pub fn is_interactive() -> bool {
+    atty::is(atty::Stream::Stdin)
+}
+
+/// Read a line from the terminal after printing `label`
+pub fn read_line(label: &str) -> PromptResult<String> {
+    if !is_interactive() {
+        return Err(format!(
+            "Cannot prompt for '{}': stdin is not a terminal",
+            label.trim_end_matches([':', ' '])
+        )
+        .into());
+    }
+    eprint!("{}", label);
+    io::stderr().flush().ok();
+    let mut value = String::new();
+    io::stdin().read_line(&mut value)?;
+    Ok(value.trim().to_string())
+}
+
+/// Read a line from the terminal without echoing it
+pub fn read_secret(label: &str) -> PromptResult<String> {
+    if !is_interactive() {
+        return Err(format!(
+            "Cannot prompt for '{}': stdin is not a terminal",
+            label.trim_end_matches([':', ' '])
+        )
+        .into());
+    }
+    Ok(rpassword::prompt_password(label)?.trim().to_string())
+}
+
+/// Prompt for the value of a placeholder, offering `last` or its default
+pub fn prompt_placeholder(placeholder: &Placeholder, last: Option<&str>) -> PromptResult<String> {
+    let default = last.or(placeholder.default.as_deref());
+
+    let mut label = placeholder.name.bold().to_string();
+    if !placeholder.choices.is_empty() {
+        label.push_str(&format!(" ({})", placeholder.choices.join("/")));
+    }
+    if let Some(default) = default {
+        if placeholder.secret {
+            label.push_str(" [****]");
+        } else {
+            label.push_str(&format!(" [{}]", default));
+        }
+    }
+    label.push_str(": ");
+
+    loop {
+        let value = if placeholder.secret {
+            read_secret(&label)?
+        } else {
+            read_line(&label)?
+        };
+        let value = if value.is_empty() {
+            default.unwrap_or_default().to_string()
+        } else {
+            value
+        };
+        if placeholder.choices.is_empty() || placeholder.choices.contains(&value) {
+            return Ok(value);
+        }
+        eprintln!("Please choose one of: {}", placeholder.choices.join(", "));
+    }
+}
+
+/// Resolve the values of placeholders.
+///
+/// When `interactive`, every placeholder is prompted for, offering the value last used
+/// for `endpoint` (secrets are never remembered). Otherwise defaults are used and a
+/// placeholder without a default is an error.
+pub fn resolve_placeholders(
+    placeholders: &[Placeholder],
+    endpoint: Option<&str>,
+    interactive: bool,
+) -> PromptResult<Vec<(String, String)>> {
+    let interactive = interactive && is_interactive();
+    let mut history: HashMap<String, HashMap<String, String>> = if interactive {
+        helper::read_state(PROMPT_HISTORY_FILE)
+    } else {
+        HashMap::new()
+    };
+    let remembered = endpoint
+        .and_then(|e| history.get(e))
+        .cloned()
+        .unwrap_or_default();
+
+    let mut values = Vec::new();
+    for placeholder in placeholders {
+        let value = if interactive {
+            let last = if placeholder.secret {
+                None
+            } else {
+                remembered.get(&placeholder.name).map(String::as_str)
+            };
+            prompt_placeholder(placeholder, last)?
+        } else if let Some(default) = &placeholder.default {
+            default.clone()
+        } else {
+            return Err(format!(
+                "Missing value for '{}': it has no default and stdin is not a terminal",
+                placeholder.name
+            )
+            .into());
+        };
+        values.push((placeholder.name.clone(), value));
+    }
+
+    if let (true, Some(endpoint)) = (interactive, endpoint) {
+        let entry = history.entry(endpoint.to_string()).or_default();
+        for (placeholder, (name, value)) in placeholders.iter().zip(values.iter()) {
+            if !placeholder.secret {
+                entry.insert(name.clone(), value.clone());
+            }
+        }
+        if let Err(e) = helper::write_state(PROMPT_HISTORY_FILE, &history) {
+            eprintln!("Failed to save prompt history: {}", e);
+        }
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_placeholders_without_terminal() {
+        let env: Placeholder = "? env | default=dev".parse().unwrap();
+        let user: Placeholder = "? user".parse().unwrap();
+
+        let values = resolve_placeholders(std::slice::from_ref(&env), Some("login"), false);
+        assert_eq!(
+            values.unwrap(),
+            vec![("env".to_string(), "dev".to_string())]
+        );
+
+        let error = resolve_placeholders(&[env, user], Some("login"), false).unwrap_err();
+        assert!(error.to_string().contains("Missing value for 'user'"));
+
+        // `cargo test < /dev/null` takes this path
+        if !is_interactive() {
+            let error = read_line("user: ").unwrap_err();
+            assert!(error.to_string().contains("stdin is not a terminal"));
+        }
+    }
+}
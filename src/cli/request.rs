@@ -3,13 +3,20 @@
 //! This module provides the command-line interface for making HTTP requests,
 //! including progress bars, colored output, and interactive prompts.
 
+use crate::cli::prompt;
 use crate::cli::request_data::RequestData;
-use crate::HttpResponse;
+use crate::core::json_path::JsonPath;
+use crate::core::template::{
+    fill_placeholders, find_placeholders, merge_placeholder, render_generators,
+};
+use crate::core::utils::path_param_names;
+use crate::models::collection::FormPart;
+use crate::{Attempt, HttpResponse, Redirect, Timings};
 use clap::Subcommand;
 use colored::{ColoredString, Colorize};
 use serde_json::Value;
 use std::fmt;
-use std::io::{self, Write};
+use std::time::Duration;
 
 #[derive(Subcommand, Clone, Debug)]
 pub enum RequestCommands {
@@ -69,16 +76,47 @@ impl RequestCommands {
         );
     }
 
-    pub fn print_request_headers(headers: &[(String, String)]) {
+    pub fn get_data_mut(&mut self) -> &mut RequestData {
+        match self {
+            Self::Get { data }
+            | Self::Post { data }
+            | Self::Put { data }
+            | Self::Delete { data }
+            | Self::Patch { data } => data,
+        }
+    }
+
+    pub fn print_request_headers(headers: &[(String, String)], secrets: &[String]) {
         println!("{}", "Request Headers:".to_string().bold().bright_blue());
         for (key, value) in headers.iter() {
-            println!("  {}: {:?}", key.to_string().bright_white(), value);
+            println!(
+                "  {}: {:?}",
+                key.to_string().bright_white(),
+                Self::mask_secrets(value, secrets)
+            );
         }
     }
 
-    pub fn print_request_body(body: &str) {
+    pub fn print_request_body(body: &str, secrets: &[String]) {
         println!("{}", "Request Body:".to_string().bold().bright_blue());
-        println!("{}", body.italic());
+        println!("{}", Self::mask_secrets(body, secrets).italic());
+    }
+
+    /// Replace the values of secret placeholders in `text` with `****`
+    pub fn mask_secrets(text: &str, secrets: &[String]) -> String {
+        secrets
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| {
+                text.replace(secret.as_str(), "****")
+            })
+    }
+
+    pub fn print_request_form(form: &[FormPart]) {
+        println!("{}", "Request Form:".to_string().bold().bright_blue());
+        for part in form.iter() {
+            println!("  {}", part.to_string().italic());
+        }
     }
 
     pub fn print_lines_with_numbers(lines: &Vec<&str>, line_numbers: &[usize]) {
@@ -89,7 +127,11 @@ impl RequestCommands {
         }
     }
 
-    pub fn print_response_body(body: &str, output: &str) {
+    pub fn print_response_body(
+        response: &HttpResponse,
+        output: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let body = &response.body;
         if output.starts_with("lines") {
             let parts: Vec<&str> = output.split(',').collect();
             let lines: Vec<&str> = body.lines().collect();
@@ -152,30 +194,31 @@ impl RequestCommands {
                     println!("{}: {}", (i + 1).to_string().bright_cyan(), line);
                 }
             }
-        } else if output.starts_with("json") {
-            // try to parse the body as JSON and look for a specific key e.g. 'json,data' to print the value of the 'data' key in the JSON response
-            let parts: Vec<&str> = output.split(',').collect();
-            if let Ok(json) = serde_json::from_str::<Value>(body) {
-                if parts.len() == 2 {
-                    let key = parts[1];
-                    if let Some(value) = json.get(key) {
-                        let pretty = serde_json::to_string_pretty(value)
-                            .unwrap_or_else(|_| value.to_string());
-                        println!("{}", pretty.green());
-                    } else {
-                        eprintln!("Key '{}' not found in JSON response.", key);
+        } else if output.starts_with("json") || output.starts_with("raw") {
+            // JSONPath query of the body e.g. 'json,$.data[*].id', or 'json,data' for a top-level key
+            let (mode, path) = output.split_once(',').unwrap_or((output, "$"));
+            let path: JsonPath = path.parse()?;
+            let values = response.query_path(&path)?;
+            if mode == "raw" {
+                // one value per line, strings without quotes
+                for value in &values {
+                    match value {
+                        Value::String(text) => println!("{}", text),
+                        value => println!("{}", value),
                     }
-                } else {
-                    let pretty =
-                        serde_json::to_string_pretty(&json).unwrap_or_else(|_| body.to_string());
-                    println!("{}", pretty.green());
                 }
+            } else if path.is_singular() {
+                let value = values
+                    .first()
+                    .ok_or_else(|| format!("'{}' not found in JSON response.", path))?;
+                println!("{}", serde_json::to_string_pretty(value)?.green());
             } else {
-                eprintln!("Failed to parse response body as JSON.");
+                println!("{}", serde_json::to_string_pretty(&values)?.green());
             }
         } else {
             println!("{}", body.italic());
         }
+        Ok(())
     }
 
     pub fn print_request_response(
@@ -187,14 +230,23 @@ impl RequestCommands {
         if verbose && !stream {
             println!("{}", "Response Headers:".to_string().bold().bright_blue());
             for (key, value) in response.headers.iter() {
-                println!("  {}: {:?}", key.to_string().bright_white(), value);
+                if key.eq_ignore_ascii_case("set-cookie") {
+                    println!(
+                        "  {}: {}",
+                        key.to_string().bold().bright_yellow(),
+                        format!("{:?}", value).yellow()
+                    );
+                } else {
+                    println!("  {}: {:?}", key.to_string().bright_white(), value);
+                }
             }
+            Self::print_timings(&response.timings);
             println!("\n{}", "Response Body:".to_string().bold().bright_blue());
         }
 
         if !stream {
             if let Some(output) = output {
-                Self::print_response_body(&response.body, output);
+                Self::print_response_body(response, output)?;
             } else {
                 //Try parsing the body as JSON
                 if let Ok(json) = response.json::<Value>() {
@@ -209,6 +261,67 @@ impl RequestCommands {
         Ok(())
     }
 
+    /// Print the request phases as a waterfall, each bar starting where the
+    /// previous phase ended
+    pub fn print_timings(timings: &Timings) {
+        const WIDTH: f64 = 40.0;
+
+        println!("{}", "Timings:".to_string().bold().bright_blue());
+        let total = timings.total.as_secs_f64().max(f64::EPSILON);
+        let phases = [
+            ("DNS lookup", timings.dns),
+            ("Connect", timings.connect),
+            ("Waiting (TTFB)", Some(timings.ttfb)),
+            ("Download", Some(timings.download)),
+        ];
+        let mut offset = Duration::ZERO;
+        for (name, duration) in phases {
+            let Some(duration) = duration else {
+                continue;
+            };
+            let start = (offset.as_secs_f64() / total * WIDTH).round() as usize;
+            let len = ((duration.as_secs_f64() / total * WIDTH).round() as usize).max(1);
+            println!(
+                "  {} {:>10}  {}{}",
+                format!("{:<15}", name).bright_white(),
+                format_ms(duration),
+                " ".repeat(start),
+                "█".repeat(len).bright_cyan()
+            );
+            offset += duration;
+        }
+        println!(
+            "  {} {:>10}",
+            format!("{:<15}", "Total").bold(),
+            format_ms(timings.total)
+        );
+    }
+
+    /// Print the failed attempts of a retried request
+    pub fn print_attempts(attempts: &[Attempt]) {
+        for (i, attempt) in attempts.iter().enumerate() {
+            println!(
+                "{} {}, retrying in {}",
+                format!("Attempt {}:", i + 1).bold().bright_yellow(),
+                attempt.outcome,
+                format_ms(attempt.delay)
+            );
+        }
+    }
+
+    /// Print the redirects followed to reach the final URL
+    pub fn print_redirects(redirects: &[Redirect]) {
+        for redirect in redirects {
+            println!(
+                "{} {} {} -> {}",
+                "Redirect:".bold().bright_yellow(),
+                Self::colorize_status(redirect.status),
+                redirect.url,
+                redirect.location
+            );
+        }
+    }
+
     pub fn colorize_status(status: u16) -> ColoredString {
         match status {
             200..=299 => status.to_string().bold().bright_green(),
@@ -218,39 +331,97 @@ impl RequestCommands {
         }
     }
 
-    pub fn prompt_missing_header_data(mut headers: Vec<(String, String)>) -> Vec<(String, String)> {
+    /// Prompt for the `{{? ...}}` placeholders in the request data and body,
+    /// then fill in their values.
+    ///
+    /// Returns the values of the secret placeholders, to be masked in verbose output.
+    pub fn fill_request_placeholders(
+        data: &mut RequestData,
+        body: &mut String,
+        interactive: bool,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut found = Vec::new();
+        for text in data.template_values().into_iter().chain([body.as_str()]) {
+            for placeholder in find_placeholders(text)? {
+                merge_placeholder(&mut found, placeholder);
+            }
+        }
+        if found.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let values = prompt::resolve_placeholders(&found, data.endpoint.as_deref(), interactive)?;
+        data.map_template_values(|text| Ok::<_, String>(fill_placeholders(text, &values)))?;
+        *body = fill_placeholders(body, &values);
+        let secrets = found
+            .iter()
+            .zip(values)
+            .filter(|(placeholder, _)| placeholder.secret)
+            .map(|(_, (_, value))| value)
+            .collect();
+        Ok(secrets)
+    }
+
+    /// Render the `{{$...}}` generators in the request data and body
+    pub fn render_request_generators(
+        data: &mut RequestData,
+        body: &mut String,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        data.map_template_values(render_generators)?;
+        *body = render_generators(body)?;
+        Ok(())
+    }
+
+    pub fn prompt_missing_header_data(
+        mut headers: Vec<(String, String)>,
+    ) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
         for header in headers.iter_mut() {
             if header.1.contains(":?") {
-                eprint!(
+                header.1 = prompt::read_line(&format!(
                     "Header value for key '{}' is missing data. Please provide the correct value: ",
                     header.0
-                );
-                io::stdout().flush().ok();
-                let mut new_value = String::new();
-                std::io::stdin()
-                    .read_line(&mut new_value)
-                    .expect("Failed to read header value");
-                header.1 = new_value.trim().to_string();
+                ))?;
+            }
+        }
+        Ok(headers)
+    }
+
+    pub fn prompt_missing_path_params(
+        url: &str,
+        mut params: Vec<(String, String)>,
+    ) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        for name in path_param_names(url) {
+            if params.iter().any(|(key, _)| *key == name) {
+                continue;
             }
+            let value = prompt::read_line(&format!("Value for path parameter '{}': ", name))?;
+            params.push((name, value));
         }
-        headers
+        Ok(params)
     }
 
-    pub fn prompt_missing_body_data(mut body: String) -> String {
+    pub fn prompt_missing_form_data(
+        mut form: Vec<FormPart>,
+    ) -> Result<Vec<FormPart>, Box<dyn std::error::Error>> {
+        for part in form.iter_mut() {
+            if let FormPart::Text { value, .. } = part {
+                *value = Self::prompt_missing_body_data(value.clone())?;
+            }
+        }
+        Ok(form)
+    }
+
+    pub fn prompt_missing_body_data(
+        mut body: String,
+    ) -> Result<String, Box<dyn std::error::Error>> {
         while let Some(idx) = body.find(":?") {
-            eprint!(
+            let replacement = prompt::read_line(&format!(
                 "Missing data at position {} - {}. Please provide the correct value: ",
                 idx, body
-            );
-            io::stdout().flush().ok();
-            let mut replacement = String::new();
-            std::io::stdin()
-                .read_line(&mut replacement)
-                .expect("Failed to read body placeholder");
-            let replacement = replacement.trim();
-            body.replace_range(idx..idx + 2, replacement);
+            ))?;
+            body.replace_range(idx..idx + 2, &replacement);
         }
-        body
+        Ok(body)
     }
 
     /// Checks if the Vec<u8> is valid UTF-8 (likely text) or not (binary).
@@ -258,3 +429,7 @@ impl RequestCommands {
         std::str::from_utf8(data).is_ok()
     }
 }
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
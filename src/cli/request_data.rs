@@ -1,6 +1,13 @@
+use std::net::IpAddr;
+
 use clap::Args;
 
-#[derive(Args, Clone, Debug)]
+use crate::models::collection::{
+    Auth, ConnectConfig, ConnectTo, FormPart, HttpProtocol, IpVersion, ProxyConfig,
+    RequestSettings, ResolveRule, RetryPolicy, TlsConfig, TlsVersion,
+};
+
+#[derive(Args, Clone, Debug, Default)]
 pub struct RequestData {
     pub url: String,
 
@@ -16,9 +23,781 @@ pub struct RequestData {
 
     #[clap(short, long, default_value = "", required = false)]
     pub body: String,
+
+    #[clap(
+        short = 'F',
+        long = "form",
+        value_parser = RequestData::parse_form_part,
+        value_name = "NAME=VALUE|NAME=@PATH[;type=MIME][;filename=NAME]",
+        num_args = 1..,
+        required = false
+    )]
+    pub form: Vec<FormPart>,
+
+    #[clap(
+        short = 'q',
+        long = "query",
+        value_parser = RequestData::parse_key_value,
+        value_name = "KEY=VALUE",
+        num_args = 1..,
+        required = false
+    )]
+    pub query: Vec<(String, String)>,
+
+    #[clap(
+        short = 'p',
+        long = "param",
+        value_parser = RequestData::parse_key_value,
+        value_name = "NAME=VALUE",
+        num_args = 1..,
+        required = false,
+        help = "Value for a {name} path parameter in the URL"
+    )]
+    pub params: Vec<(String, String)>,
+
+    #[clap(
+        short = 'U',
+        long = "urlencoded",
+        value_parser = RequestData::parse_key_value,
+        value_name = "KEY=VALUE",
+        num_args = 1..,
+        required = false,
+        help = "Send an application/x-www-form-urlencoded body built from key-value pairs"
+    )]
+    pub urlencoded: Vec<(String, String)>,
+
+    #[clap(
+        short = 'a',
+        long = "auth",
+        value_parser = RequestData::parse_auth,
+        value_name = "AUTH",
+        help = "Auth: none, basic:USER:PASSWORD, bearer:TOKEN, apikey:[header|query:]NAME:VALUE, oauth2:OPTIONS, sigv4:ACCESS_KEY:SECRET_KEY:REGION:SERVICE[:SESSION_TOKEN], hmac:OPTIONS, jwt:OPTIONS or digest:USER:PASSWORD",
+        required = false
+    )]
+    pub auth: Option<Auth>,
+
+    #[clap(
+        long = "unix-socket",
+        value_name = "PATH",
+        help = "Send the request over a Unix domain socket",
+        required = false
+    )]
+    pub unix_socket: Option<String>,
+
+    #[clap(flatten)]
+    pub tls: TlsArgs,
+
+    #[clap(flatten)]
+    pub proxy: ProxyArgs,
+
+    #[clap(flatten)]
+    pub connect: ConnectArgs,
+
+    #[clap(flatten)]
+    pub protocol: ProtocolArgs,
+
+    #[clap(flatten)]
+    pub retry: RetryArgs,
+
+    #[clap(flatten)]
+    pub settings: SettingsArgs,
+
+    #[clap(
+        long = "trace",
+        value_name = "FILE|-",
+        help = "Write the raw request and response to a file, or '-' for stdout",
+        required = false
+    )]
+    pub trace: Option<String>,
+
+    /// Saved endpoint ("collection/endpoint") the request was loaded from
+    #[clap(skip)]
+    pub endpoint: Option<String>,
+
+    /// Collection whose cookie jar the request uses
+    #[clap(skip)]
+    pub cookie_jar: Option<String>,
+}
+
+// -k, --cacert, --cert, --key, --cert-password and --tls-min. The option groups
+// flattened into commands have plain comments: a doc comment would become the
+// help text of every command flattening them.
+#[derive(Args, Clone, Debug, Default)]
+pub struct TlsArgs {
+    #[clap(
+        short = 'k',
+        long,
+        default_value = "false",
+        help = "Skip TLS certificate and host name verification"
+    )]
+    pub insecure: bool,
+
+    #[clap(
+        long = "cacert",
+        value_name = "PEM",
+        help = "Trust the CA certificates of a PEM file, besides the system roots",
+        required = false
+    )]
+    pub ca_cert: Option<String>,
+
+    #[clap(
+        long = "cert",
+        value_name = "PEM|P12",
+        help = "Client certificate: PEM, with --key or its key included, or PKCS#12",
+        required = false
+    )]
+    pub client_cert: Option<String>,
+
+    #[clap(
+        long = "key",
+        value_name = "PEM",
+        help = "Private key of a PEM client certificate",
+        required = false
+    )]
+    pub client_key: Option<String>,
+
+    #[clap(
+        long = "cert-password",
+        value_name = "PASSWORD",
+        help = "Password of a PKCS#12 client certificate",
+        required = false
+    )]
+    pub client_cert_password: Option<String>,
+
+    #[clap(
+        long = "tls-min",
+        value_name = "VERSION",
+        help = "Lowest TLS version accepted: 1.0, 1.1, 1.2 or 1.3",
+        required = false
+    )]
+    pub min_version: Option<TlsVersion>,
+}
+
+impl From<&TlsArgs> for TlsConfig {
+    fn from(args: &TlsArgs) -> Self {
+        TlsConfig {
+            ca_cert: args.ca_cert.clone(),
+            insecure: args.insecure,
+            client_cert: args.client_cert.clone(),
+            client_key: args.client_key.clone(),
+            client_cert_password: args.client_cert_password.clone(),
+            min_version: args.min_version,
+        }
+    }
+}
+
+impl From<TlsConfig> for TlsArgs {
+    fn from(tls: TlsConfig) -> Self {
+        TlsArgs {
+            insecure: tls.insecure,
+            ca_cert: tls.ca_cert,
+            client_cert: tls.client_cert,
+            client_key: tls.client_key,
+            client_cert_password: tls.client_cert_password,
+            min_version: tls.min_version,
+        }
+    }
+}
+
+/// A secret flag value as a `{{? name | secret}}` placeholder, prompted for when
+/// the flag is used, unless it already is a template expression
+fn secret_placeholder(value: &str, name: &str) -> String {
+    if value.contains("{{") {
+        value.to_string()
+    } else {
+        format!("{{{{? {} | secret}}}}", name)
+    }
+}
+
+impl TlsArgs {
+    /// The options as command-line flags
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.insecure {
+            flags.push("-k".to_string());
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            flags.push(format!("--cacert '{}'", ca_cert));
+        }
+        if let Some(client_cert) = &self.client_cert {
+            flags.push(format!("--cert '{}'", client_cert));
+        }
+        if let Some(client_key) = &self.client_key {
+            flags.push(format!("--key '{}'", client_key));
+        }
+        if let Some(password) = &self.client_cert_password {
+            flags.push(format!(
+                "--cert-password '{}'",
+                secret_placeholder(password, "cert_password")
+            ));
+        }
+        if let Some(min_version) = self.min_version {
+            flags.push(format!("--tls-min {}", min_version));
+        }
+        flags
+    }
+}
+
+// Proxy URL and credentials, or --no-proxy to ignore the collection proxy
+#[derive(Args, Clone, Debug, Default)]
+pub struct ProxyArgs {
+    #[clap(
+        long = "proxy",
+        value_name = "URL",
+        help = "Proxy: http|https|socks5|socks5h://[USER:PASSWORD@]HOST[:PORT], or none for a direct connection",
+        required = false
+    )]
+    pub proxy: Option<String>,
+
+    #[clap(
+        long = "proxy-user",
+        value_parser = ProxyArgs::parse_proxy_user,
+        value_name = "USER:PASSWORD",
+        help = "Proxy username and password",
+        required = false
+    )]
+    pub proxy_user: Option<(String, String)>,
+
+    #[clap(
+        long = "no-proxy",
+        value_name = "HOSTS",
+        value_delimiter = ',',
+        help = "Comma-separated hosts, domains, IPs or CIDR ranges reached without the proxy",
+        required = false
+    )]
+    pub no_proxy: Vec<String>,
+}
+
+impl From<ProxyConfig> for ProxyArgs {
+    fn from(proxy: ProxyConfig) -> Self {
+        ProxyArgs {
+            proxy: Some(proxy.url),
+            proxy_user: proxy
+                .username
+                .map(|username| (username, proxy.password.unwrap_or_default())),
+            no_proxy: proxy.no_proxy,
+        }
+    }
+}
+
+impl ProxyArgs {
+    /// Merge command options over these ones. A new proxy URL comes with its
+    /// own credentials and no-proxy list.
+    pub fn merge(&self, other: &ProxyArgs) -> ProxyArgs {
+        if other.proxy.is_some() {
+            return other.clone();
+        }
+        ProxyArgs {
+            proxy: self.proxy.clone(),
+            proxy_user: other.proxy_user.clone().or_else(|| self.proxy_user.clone()),
+            no_proxy: if other.no_proxy.is_empty() {
+                self.no_proxy.clone()
+            } else {
+                other.no_proxy.clone()
+            },
+        }
+    }
+
+    /// The proxy configured, if any
+    pub fn config(&self) -> Result<Option<ProxyConfig>, String> {
+        let Some(url) = &self.proxy else {
+            if self.proxy_user.is_some() || !self.no_proxy.is_empty() {
+                return Err("--proxy-user and --no-proxy need a --proxy URL".to_string());
+            }
+            return Ok(None);
+        };
+        Ok(Some(ProxyConfig {
+            url: url.clone(),
+            username: self
+                .proxy_user
+                .as_ref()
+                .map(|(username, _)| username.clone()),
+            password: self
+                .proxy_user
+                .as_ref()
+                .map(|(_, password)| password.clone())
+                .filter(|password| !password.is_empty()),
+            no_proxy: self.no_proxy.clone(),
+        }))
+    }
+
+    /// The options as command-line flags
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(url) = &self.proxy {
+            let password = url::Url::parse(url)
+                .ok()
+                .and_then(|u| u.password().map(str::to_string));
+            let url = match password {
+                Some(password) => url.replacen(
+                    &format!(":{}@", password),
+                    &format!(":{}@", secret_placeholder(&password, "proxy_password")),
+                    1,
+                ),
+                None => url.clone(),
+            };
+            flags.push(format!("--proxy '{}'", url));
+        }
+        if let Some((username, password)) = &self.proxy_user {
+            flags.push(format!(
+                "--proxy-user '{}:{}'",
+                username,
+                secret_placeholder(password, "proxy_password")
+            ));
+        }
+        if !self.no_proxy.is_empty() {
+            flags.push(format!("--no-proxy '{}'", self.no_proxy.join(",")));
+        }
+        flags
+    }
+
+    pub fn parse_proxy_user(s: &str) -> Result<(String, String), String> {
+        let (username, password) = s.split_once(':').unwrap_or((s, ""));
+        if username.is_empty() {
+            return Err(format!("Invalid proxy user: '{}'. Use USER:PASSWORD", s));
+        }
+        Ok((username.to_string(), password.to_string()))
+    }
+}
+
+// DNS overrides, connection targets, IP version and local address
+#[derive(Args, Clone, Debug, Default)]
+pub struct ConnectArgs {
+    #[clap(
+        long = "resolve",
+        value_name = "HOST:PORT:ADDR[,ADDR]",
+        help = "Resolve HOST on PORT (* for any) to the given addresses",
+        required = false
+    )]
+    pub resolve: Vec<ResolveRule>,
+
+    #[clap(
+        long = "connect-to",
+        value_name = "HOST1:PORT1:HOST2:PORT2",
+        help = "Connect to HOST2:PORT2 for requests to HOST1:PORT1, empty fields matching or keeping any",
+        required = false
+    )]
+    pub connect_to: Vec<ConnectTo>,
+
+    #[clap(
+        short = '4',
+        long = "ipv4",
+        default_value = "false",
+        conflicts_with = "ipv6",
+        help = "Only connect over IPv4"
+    )]
+    pub ipv4: bool,
+
+    #[clap(
+        short = '6',
+        long = "ipv6",
+        default_value = "false",
+        help = "Only connect over IPv6"
+    )]
+    pub ipv6: bool,
+
+    #[clap(
+        long = "local-address",
+        value_name = "IP",
+        help = "Local address to connect from",
+        required = false
+    )]
+    pub local_address: Option<IpAddr>,
+}
+
+impl From<&ConnectArgs> for ConnectConfig {
+    fn from(args: &ConnectArgs) -> Self {
+        ConnectConfig {
+            resolve: args.resolve.clone(),
+            connect_to: args.connect_to.clone(),
+            ip_version: if args.ipv4 {
+                Some(IpVersion::Ipv4)
+            } else if args.ipv6 {
+                Some(IpVersion::Ipv6)
+            } else {
+                None
+            },
+            local_address: args.local_address,
+        }
+    }
+}
+
+impl From<ConnectConfig> for ConnectArgs {
+    fn from(connect: ConnectConfig) -> Self {
+        ConnectArgs {
+            resolve: connect.resolve,
+            connect_to: connect.connect_to,
+            ipv4: connect.ip_version == Some(IpVersion::Ipv4),
+            ipv6: connect.ip_version == Some(IpVersion::Ipv6),
+            local_address: connect.local_address,
+        }
+    }
+}
+
+impl ConnectArgs {
+    /// The options as command-line flags
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        for rule in &self.resolve {
+            flags.push(format!("--resolve '{}'", rule));
+        }
+        for rule in &self.connect_to {
+            flags.push(format!("--connect-to '{}'", rule));
+        }
+        if self.ipv4 {
+            flags.push("-4".to_string());
+        }
+        if self.ipv6 {
+            flags.push("-6".to_string());
+        }
+        if let Some(address) = self.local_address {
+            flags.push(format!("--local-address {}", address));
+        }
+        flags
+    }
+}
+
+// --http1.1 or --http2-prior-knowledge instead of the negotiated version
+#[derive(Args, Clone, Debug, Default)]
+pub struct ProtocolArgs {
+    #[clap(
+        long = "http1.1",
+        default_value = "false",
+        conflicts_with = "http2_prior_knowledge",
+        help = "Only use HTTP/1.1"
+    )]
+    pub http1_1: bool,
+
+    #[clap(
+        long = "http2-prior-knowledge",
+        default_value = "false",
+        help = "Use HTTP/2 without negotiating it, also over plain HTTP"
+    )]
+    pub http2_prior_knowledge: bool,
+}
+
+impl From<Option<HttpProtocol>> for ProtocolArgs {
+    fn from(protocol: Option<HttpProtocol>) -> Self {
+        ProtocolArgs {
+            http1_1: protocol == Some(HttpProtocol::Http1_1),
+            http2_prior_knowledge: protocol == Some(HttpProtocol::Http2PriorKnowledge),
+        }
+    }
+}
+
+impl ProtocolArgs {
+    /// The HTTP version chosen, if any
+    pub fn protocol(&self) -> Option<HttpProtocol> {
+        if self.http1_1 {
+            Some(HttpProtocol::Http1_1)
+        } else if self.http2_prior_knowledge {
+            Some(HttpProtocol::Http2PriorKnowledge)
+        } else {
+            None
+        }
+    }
+
+    /// The options as command-line flags
+    pub fn flags(&self) -> Vec<String> {
+        match self.protocol() {
+            Some(HttpProtocol::Http1_1) => vec!["--http1.1".to_string()],
+            Some(HttpProtocol::Http2PriorKnowledge) => {
+                vec!["--http2-prior-knowledge".to_string()]
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+// Attempts, retried statuses and backoff delays of a retried request
+#[derive(Args, Clone, Debug, Default)]
+pub struct RetryArgs {
+    #[clap(
+        long = "retry",
+        value_name = "ATTEMPTS",
+        help = "Make up to ATTEMPTS attempts when the request fails with a connection error, a timeout or a retried status",
+        required = false
+    )]
+    pub retry: Option<u32>,
+
+    #[clap(
+        long = "retry-status",
+        value_name = "CODES",
+        value_delimiter = ',',
+        help = "Comma-separated statuses retried [default: 429,502,503,504]",
+        required = false
+    )]
+    pub retry_status: Vec<u16>,
+
+    #[clap(
+        long = "retry-delay",
+        value_name = "MS",
+        help = "Delay before the first retry in milliseconds, doubled for each next one [default: 500]",
+        required = false
+    )]
+    pub retry_delay: Option<u64>,
+
+    #[clap(
+        long = "retry-max-delay",
+        value_name = "MS",
+        help = "Longest delay between attempts in milliseconds, also capping Retry-After [default: 30000]",
+        required = false
+    )]
+    pub retry_max_delay: Option<u64>,
+}
+
+impl From<Option<RetryPolicy>> for RetryArgs {
+    fn from(policy: Option<RetryPolicy>) -> Self {
+        match policy {
+            Some(policy) => RetryArgs {
+                retry: Some(policy.max_attempts),
+                retry_status: policy.statuses,
+                retry_delay: Some(policy.delay_ms),
+                retry_max_delay: Some(policy.max_delay_ms),
+            },
+            None => RetryArgs::default(),
+        }
+    }
+}
+
+impl RetryArgs {
+    /// Merge command options over these ones
+    pub fn merge(&self, other: &RetryArgs) -> RetryArgs {
+        RetryArgs {
+            retry: other.retry.or(self.retry),
+            retry_status: if other.retry_status.is_empty() {
+                self.retry_status.clone()
+            } else {
+                other.retry_status.clone()
+            },
+            retry_delay: other.retry_delay.or(self.retry_delay),
+            retry_max_delay: other.retry_max_delay.or(self.retry_max_delay),
+        }
+    }
+
+    /// The retry policy configured, if any
+    pub fn policy(&self) -> Result<Option<RetryPolicy>, String> {
+        let Some(max_attempts) = self.retry else {
+            if !self.retry_status.is_empty()
+                || self.retry_delay.is_some()
+                || self.retry_max_delay.is_some()
+            {
+                return Err("--retry-status and --retry-delay options need --retry".to_string());
+            }
+            return Ok(None);
+        };
+        if max_attempts == 0 {
+            return Err("--retry needs at least 1 attempt".to_string());
+        }
+        let mut policy = RetryPolicy::new(max_attempts);
+        if !self.retry_status.is_empty() {
+            policy.statuses = self.retry_status.clone();
+        }
+        if let Some(delay) = self.retry_delay {
+            policy.delay_ms = delay;
+        }
+        if let Some(max_delay) = self.retry_max_delay {
+            policy.max_delay_ms = max_delay;
+        }
+        Ok(Some(policy))
+    }
+
+    /// The options as command-line flags
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(attempts) = self.retry {
+            flags.push(format!("--retry {}", attempts));
+        }
+        if !self.retry_status.is_empty() {
+            let statuses: Vec<String> = self.retry_status.iter().map(|s| s.to_string()).collect();
+            flags.push(format!("--retry-status {}", statuses.join(",")));
+        }
+        if let Some(delay) = self.retry_delay {
+            flags.push(format!("--retry-delay {}", delay));
+        }
+        if let Some(max_delay) = self.retry_max_delay {
+            flags.push(format!("--retry-max-delay {}", max_delay));
+        }
+        flags
+    }
+}
+
+// Request timeouts and redirect following, stored as RequestSettings
+#[derive(Args, Clone, Debug, Default)]
+pub struct SettingsArgs {
+    #[clap(
+        long = "timeout",
+        value_parser = SettingsArgs::parse_seconds,
+        value_name = "SECONDS",
+        help = "Timeout of the whole request in seconds [default: 120]",
+        required = false
+    )]
+    pub timeout: Option<u64>,
+
+    #[clap(
+        long = "connect-timeout",
+        value_parser = SettingsArgs::parse_seconds,
+        value_name = "SECONDS",
+        help = "Timeout of opening the connection in seconds",
+        required = false
+    )]
+    pub connect_timeout: Option<u64>,
+
+    #[clap(
+        short = 'L',
+        long = "location",
+        default_value = "false",
+        conflicts_with = "no_location",
+        help = "Follow redirects"
+    )]
+    pub location: bool,
+
+    #[clap(
+        long = "no-location",
+        default_value = "false",
+        help = "Return redirects instead of following them"
+    )]
+    pub no_location: bool,
+
+    #[clap(
+        long = "max-redirs",
+        value_name = "N",
+        help = "Most redirects followed before failing [default: 10]",
+        required = false
+    )]
+    pub max_redirs: Option<usize>,
+}
+
+impl From<&SettingsArgs> for RequestSettings {
+    fn from(args: &SettingsArgs) -> Self {
+        RequestSettings {
+            timeout_ms: args.timeout,
+            connect_timeout_ms: args.connect_timeout,
+            follow_redirects: if args.location {
+                Some(true)
+            } else if args.no_location {
+                Some(false)
+            } else {
+                None
+            },
+            max_redirects: args.max_redirs,
+        }
+    }
+}
+
+impl From<RequestSettings> for SettingsArgs {
+    fn from(settings: RequestSettings) -> Self {
+        SettingsArgs {
+            timeout: settings.timeout_ms,
+            connect_timeout: settings.connect_timeout_ms,
+            location: settings.follow_redirects == Some(true),
+            no_location: settings.follow_redirects == Some(false),
+            max_redirs: settings.max_redirects,
+        }
+    }
+}
+
+impl SettingsArgs {
+    /// The options as command-line flags
+    pub fn flags(&self) -> Vec<String> {
+        let seconds = |ms: u64| std::time::Duration::from_millis(ms).as_secs_f64();
+        let mut flags = Vec::new();
+        if let Some(timeout) = self.timeout {
+            flags.push(format!("--timeout {}", seconds(timeout)));
+        }
+        if let Some(timeout) = self.connect_timeout {
+            flags.push(format!("--connect-timeout {}", seconds(timeout)));
+        }
+        if self.location {
+            flags.push("-L".to_string());
+        }
+        if self.no_location {
+            flags.push("--no-location".to_string());
+        }
+        if let Some(max) = self.max_redirs {
+            flags.push(format!("--max-redirs {}", max));
+        }
+        flags
+    }
+
+    /// Parse a duration in seconds, fractions allowed, into milliseconds
+    pub fn parse_seconds(s: &str) -> Result<u64, String> {
+        let seconds: f64 = s
+            .parse()
+            .map_err(|_| format!("Invalid duration: '{}'. Use SECONDS, like 2.5", s))?;
+        if !seconds.is_finite() || seconds <= 0.0 {
+            return Err(format!("Invalid duration: '{}'. It must be positive", s));
+        }
+        Ok((seconds * 1000.0).round().max(1.0) as u64)
+    }
 }
 
 impl RequestData {
+    /// Get the text values that may contain template expressions
+    pub fn template_values(&self) -> Vec<&str> {
+        let mut values = vec![self.url.as_str()];
+        values.extend(self.headers.iter().map(|(_, v)| v.as_str()));
+        values.extend(self.query.iter().map(|(_, v)| v.as_str()));
+        values.extend(self.params.iter().map(|(_, v)| v.as_str()));
+        values.extend(self.urlencoded.iter().map(|(_, v)| v.as_str()));
+        for part in &self.form {
+            match part {
+                FormPart::Text { value, .. } => values.push(value),
+                FormPart::File { path, .. } => values.push(path),
+            }
+        }
+        if let Some(auth) = &self.auth {
+            values.extend(auth.values());
+        }
+        values.extend(self.tls.client_cert_password.as_deref());
+        values.extend(self.proxy.proxy.as_deref());
+        values.extend(
+            self.proxy
+                .proxy_user
+                .iter()
+                .map(|(_, password)| password.as_str()),
+        );
+        values
+    }
+
+    /// Apply `f` to every text value that may contain template expressions
+    pub fn map_template_values<F, E>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&str) -> Result<String, E>,
+    {
+        self.url = f(&self.url)?;
+        for (_, value) in self
+            .headers
+            .iter_mut()
+            .chain(self.query.iter_mut())
+            .chain(self.params.iter_mut())
+            .chain(self.urlencoded.iter_mut())
+        {
+            *value = f(value)?;
+        }
+        for part in self.form.iter_mut() {
+            match part {
+                FormPart::Text { value, .. } => *value = f(value)?,
+                FormPart::File { path, .. } => *path = f(path)?,
+            }
+        }
+        if let Some(auth) = self.auth.as_mut() {
+            for value in auth.values_mut() {
+                *value = f(value)?;
+            }
+        }
+        if let Some(password) = self.tls.client_cert_password.as_mut() {
+            *password = f(password)?;
+        }
+        if let Some(url) = self.proxy.proxy.as_mut() {
+            *url = f(url)?;
+        }
+        if let Some((_, password)) = self.proxy.proxy_user.as_mut() {
+            *password = f(password)?;
+        }
+        Ok(())
+    }
+
     pub fn parse_header(s: &str) -> Result<(String, String), String> {
         let parts: Vec<&str> = s.splitn(2, ':').collect();
         if parts.len() != 2 {
@@ -26,4 +805,19 @@ impl RequestData {
         }
         Ok((parts[0].trim().to_string(), parts[1].trim().to_string()))
     }
+
+    pub fn parse_key_value(s: &str) -> Result<(String, String), String> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid format: '{}'. Use KEY=VALUE", s))?;
+        Ok((key.trim().to_string(), value.to_string()))
+    }
+
+    pub fn parse_form_part(s: &str) -> Result<FormPart, String> {
+        s.parse()
+    }
+
+    pub fn parse_auth(s: &str) -> Result<Auth, String> {
+        s.parse()
+    }
 }
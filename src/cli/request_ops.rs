@@ -1,8 +1,14 @@
-use std::{io::Write, time::Duration};
+use std::{io::Write, sync::Arc, time::Duration};
 
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::multipart::Part;
 
+use crate::cli::format::OutputFormat;
+use crate::cli::output::OutputArgs;
+use crate::core::config::Config;
+use crate::core::cookies::CookieJar;
+use crate::core::trace::Trace;
+use crate::core::utils::encode_form;
+use crate::models::collection::{ConnectConfig, RequestSettings, TlsConfig};
 use crate::{cli::request::RequestCommands, HttpClient, HttpMethod, HttpResponse};
 
 impl RequestCommands {
@@ -11,63 +17,116 @@ impl RequestCommands {
         verbose: bool,
         stdin_input: Vec<u8>,
         stream: bool,
-    ) -> Result<(HttpResponse, u128), Box<dyn std::error::Error>> {
-        let data = self.get_data();
+        progress: bool,
+    ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+        let mut data = self.get_data().clone();
+
+        let is_text = Self::is_text_data(&stdin_input);
+        let mut body = if stdin_input.is_empty() {
+            data.body.clone()
+        } else if is_text {
+            // Convert to string for text processing
+            String::from_utf8_lossy(&stdin_input).to_string()
+        } else {
+            // Binary: skip text prompts, use as-is (but reqwest body will handle bytes)
+            String::new() // Placeholder; we'll use bytes directly in the request
+        };
+
+        let secrets = Self::fill_request_placeholders(&mut data, &mut body, !stream)?;
+        Self::render_request_generators(&mut data, &mut body)?;
 
         let current_url = if !stream {
-            RequestCommands::prompt_missing_body_data(data.url.clone())
+            RequestCommands::prompt_missing_body_data(data.url.clone())?
         } else {
             data.url.clone()
         };
 
         let headers = if !stream {
-            Self::prompt_missing_header_data(data.headers.clone())
+            Self::prompt_missing_header_data(data.headers.clone())?
         } else {
             data.headers.clone()
         };
 
-        let is_text = Self::is_text_data(&stdin_input);
-        let body = if stdin_input.is_empty() {
-            Self::prompt_missing_body_data(data.body.clone())
-        } else if is_text {
-            // Convert to string for text processing
-            let text = String::from_utf8_lossy(&stdin_input).to_string();
-            Self::prompt_missing_body_data(text)
+        let form = if !stream {
+            Self::prompt_missing_form_data(data.form.clone())?
         } else {
-            // Binary: skip text prompts, use as-is (but reqwest body will handle bytes)
-            String::new() // Placeholder; we'll use bytes directly in the request
+            data.form.clone()
         };
 
-        let part = if !stream && !stdin_input.is_empty() && !is_text {
-            // Binary data from stdin
+        let params = if !stream {
+            Self::prompt_missing_path_params(&current_url, data.params.clone())?
+        } else {
+            data.params.clone()
+        };
+
+        let body = if !stream && stdin_input.is_empty() {
+            Self::prompt_missing_body_data(body)?
+        } else {
+            body
+        };
+
+        let upload = if !stream && !stdin_input.is_empty() && !is_text {
+            // Binary data from stdin, sent as the file part of a multipart form
             let kind = infer::get(&stdin_input).ok_or_else(|| {
                 Box::new(std::io::Error::new(
                     std::io::ErrorKind::InvalidData,
                     "Unknown file type",
                 ))
             })?;
-            let mime_type = kind.mime_type();
-            let extension = kind.extension();
-            let filename = format!("file.{}", extension);
-            Part::bytes(stdin_input.clone())
-                .file_name(filename)
-                .mime_str(mime_type)?
-        } else if !stream && !stdin_input.is_empty() && is_text {
-            // Text data from stdin
-            Part::text(String::from_utf8_lossy(&stdin_input).to_string())
+            Some((format!("file.{}", kind.extension()), kind.mime_type()))
         } else {
-            // Use body string
-            Part::bytes(body.clone().into_bytes())
+            None
         };
 
+        let query = data.query.clone();
+        let urlencoded = data.urlencoded.clone();
+        let auth = data.auth.clone();
+        let cookie_jar = data
+            .cookie_jar
+            .as_deref()
+            .map(|collection| Arc::new(CookieJar::load(collection)));
+
         if verbose && !stream {
-            Self::print_request_headers(&headers);
-            Self::print_request_body(body.as_str());
+            Self::print_request_headers(&headers, &secrets);
+            if !form.is_empty() && stdin_input.is_empty() {
+                Self::print_request_form(&form);
+            } else if !urlencoded.is_empty() && stdin_input.is_empty() {
+                Self::print_request_body(&encode_form(&urlencoded), &secrets);
+            } else {
+                Self::print_request_body(body.as_str(), &secrets);
+            }
         }
 
         let client = HttpClient::new()
-            .with_follow_redirects(false)
-            .with_timeout(Duration::from_secs(120));
+            .with_timeout(Duration::from_secs(120))
+            .with_settings(&Config::global().settings())
+            .with_settings(&RequestSettings::from(&data.settings))
+            .with_tls(TlsConfig::from(&data.tls))
+            .with_connect(ConnectConfig::from(&data.connect));
+        let client = match data.proxy.config()? {
+            Some(proxy) => client.with_proxy(proxy),
+            None => client,
+        };
+        let client = match data.protocol.protocol() {
+            Some(protocol) => client.with_protocol(protocol),
+            None => client,
+        };
+        let client = match &data.unix_socket {
+            Some(socket) => client.with_unix_socket(socket),
+            None => client,
+        };
+        let client = match data.retry.policy()? {
+            Some(retry) => client.with_retry(retry),
+            None => client,
+        };
+        let client = match &data.trace {
+            Some(path) => {
+                let trace = Trace::open(path)
+                    .map_err(|e| format!("Cannot open trace file '{}': {}", path, e))?;
+                client.with_trace(Arc::new(trace))
+            }
+            None => client,
+        };
 
         let method = match self {
             Self::Get { .. } => HttpMethod::Get,
@@ -77,7 +136,11 @@ impl RequestCommands {
             Self::Patch { .. } => HttpMethod::Patch,
         };
 
-        let pb = ProgressBar::new_spinner();
+        let pb = if progress {
+            ProgressBar::new_spinner()
+        } else {
+            ProgressBar::hidden()
+        };
 
         pb.set_style(
             ProgressStyle::with_template("{spinner:.green} {elapsed} {msg}")
@@ -88,7 +151,13 @@ impl RequestCommands {
         pb.enable_steady_tick(Duration::from_millis(80));
         pb.set_message("Executing Request...");
 
-        let start = std::time::Instant::now();
+        let request = client
+            .request(method, &current_url)
+            .headers(headers)
+            .query(query)
+            .path_params(params)
+            .auth(auth)
+            .cookie_jar(cookie_jar);
 
         let resp = if stream {
             let body_bytes = if !stdin_input.is_empty() {
@@ -96,9 +165,7 @@ impl RequestCommands {
             } else {
                 body.clone().into_bytes()
             };
-            client
-                .request(method, &current_url)
-                .headers(headers.into_iter().collect())
+            request
                 .body_bytes(body_bytes)
                 .send_streaming(|chunk| {
                     std::io::stdout().write_all(chunk)?;
@@ -106,32 +173,23 @@ impl RequestCommands {
                     Ok(())
                 })
                 .await
-        } else if is_text {
-            let body_text = if !stdin_input.is_empty() {
-                String::from_utf8_lossy(&stdin_input).to_string()
-            } else {
-                body
-            };
-            client
-                .request(method, &current_url)
-                .headers(headers.into_iter().collect())
-                .body(&body_text)
+        } else if !form.is_empty() && stdin_input.is_empty() {
+            request.multipart(form).send().await
+        } else if !urlencoded.is_empty() && stdin_input.is_empty() {
+            request.form(urlencoded).send().await
+        } else if let Some((file_name, mime_type)) = upload {
+            request
+                .upload(stdin_input, &file_name, mime_type)
                 .send()
                 .await
         } else {
-            client
-                .request(method, &current_url)
-                .headers(headers.into_iter().collect())
-                .send_multipart(part)
-                .await
+            request.body(&body).send().await
         };
 
-        let elapsed = start.elapsed().as_millis();
-
         match resp {
             Ok(response) => {
                 pb.finish_with_message("Request completed");
-                Ok((response, elapsed))
+                Ok(response)
             }
             Err(err) => {
                 pb.finish_with_message("Request failed");
@@ -146,18 +204,139 @@ impl RequestCommands {
         stdin_input: Vec<u8>,
         stream: bool,
         output: &Option<String>,
+        format: OutputFormat,
+        select: &OutputArgs,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let response = Self::execute_request(self, verbose, stdin_input, stream).await;
-
-        match response {
-            Ok((resp, elapsed)) => {
-                if verbose && !stream {
-                    println!("{:?}", resp.version);
-                    self.print_request_method(&resp.url, resp.status, elapsed);
-                }
-                Self::print_request_response(&resp, verbose, stream, output)
+        select.check(format, stream)?;
+        if !format.is_text() {
+            if stream {
+                return Err("--stream writes the raw body and cannot be used with --format".into());
+            }
+            if self.get_data().trace.as_deref() == Some("-") {
+                return Err(
+                    "--trace - writes to stdout and cannot be used with --format: trace to a file"
+                        .into(),
+                );
             }
-            Err(err) => Err(err),
+            let response = Self::execute_request(self, false, stdin_input, false, false).await?;
+            format.print(&response)?;
+            return select.finish(&response);
+        }
+
+        if let Some(part) = &select.only {
+            let response = Self::execute_request(self, false, stdin_input, false, true).await?;
+            part.print(&response)?;
+            return select.finish(&response);
         }
+
+        let resp = Self::execute_request(self, verbose, stdin_input, stream, true).await?;
+        if verbose && !stream {
+            Self::print_attempts(&resp.attempts);
+            Self::print_redirects(&resp.redirects);
+            println!("{}", resp.version);
+            self.print_request_method(&resp.url, resp.status, resp.elapsed_ms);
+        }
+        Self::print_request_response(&resp, verbose, stream, output)?;
+        select.finish(&resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use url::Url;
+
+    use super::*;
+    use crate::cli::request_data::RequestData;
+    use crate::core::test_server::serve;
+    use crate::models::collection::{Auth, HmacAuth};
+
+    #[test]
+    fn test_secret_placeholders_are_masked() {
+        let mut data = RequestData {
+            url: "http://127.0.0.1:9/".to_string(),
+            headers: vec![
+                (
+                    "Authorization".to_string(),
+                    "Bearer {{? token | secret | default=abc123}}".to_string(),
+                ),
+                ("X-Env".to_string(), "{{? env | default=dev}}".to_string()),
+            ],
+            ..Default::default()
+        };
+        let mut body = String::new();
+        let secrets =
+            RequestCommands::fill_request_placeholders(&mut data, &mut body, false).unwrap();
+        assert_eq!(secrets, vec!["abc123"]);
+        assert_eq!(data.headers[0].1, "Bearer abc123");
+        assert_eq!(
+            RequestCommands::mask_secrets(&data.headers[0].1, &secrets),
+            "Bearer ****"
+        );
+        assert_eq!(
+            RequestCommands::mask_secrets(&data.headers[1].1, &secrets),
+            "dev"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_format_refuses_stdout_trace() {
+        let command = RequestCommands::Get {
+            data: RequestData {
+                url: "http://127.0.0.1:9/".to_string(),
+                trace: Some("-".to_string()),
+                ..Default::default()
+            },
+        };
+        for format in [OutputFormat::Json, OutputFormat::Ndjson] {
+            let error = command
+                .run(
+                    false,
+                    Vec::new(),
+                    false,
+                    &None,
+                    format,
+                    &OutputArgs::default(),
+                )
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("--trace -"), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signs_binary_stdin() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let addr = serve(move |request, stream| {
+            received.lock().unwrap().push(request);
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+        });
+        let url = format!("http://{}/upload", addr);
+        let hmac: HmacAuth = "secret=whsec".parse().unwrap();
+        let command = RequestCommands::Post {
+            data: RequestData {
+                url: url.clone(),
+                auth: Some(Auth::Hmac(hmac.clone())),
+                ..Default::default()
+            },
+        };
+
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR".to_vec();
+        let response = command
+            .execute_request(false, png.clone(), false, false)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        let requests = requests.lock().unwrap();
+        let timestamp: u64 = requests[0].header("x-timestamp").unwrap().parse().unwrap();
+        let expected = hmac.sign("POST", &Url::parse(&url).unwrap(), &png, timestamp);
+        assert_eq!(
+            requests[0].header("x-signature"),
+            Some(expected[1].1.as_str())
+        );
+        assert!(requests[0].text().contains("filename=\"file.png\""));
     }
 }
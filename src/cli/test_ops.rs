@@ -3,12 +3,31 @@
 //! This module provides the test command functionality for the CLI.
 
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::cli::{commands::Commands, manager::ManagerCommands, request::RequestCommands};
+use crate::cli::{
+    commands::Commands, format::OutputFormat, manager::ManagerCommands, request::RequestCommands,
+};
 use crate::core::collection_manager::CollectionManager;
 
+/// Result of one endpoint, for the machine-readable formats
+#[derive(Serialize)]
+struct TestResult {
+    endpoint: String,
+    method: String,
+    url: Option<String>,
+    status: Option<u16>,
+    elapsed_ms: Option<u128>,
+    attempts: Option<usize>,
+    error: Option<String>,
+}
+
 impl Commands {
-    pub async fn run_tests(&self, collection_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn run_tests(
+        &self,
+        collection_name: &str,
+        format: OutputFormat,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let manager = CollectionManager::default();
         let collection = manager
             .get_collection(collection_name)
@@ -16,6 +35,7 @@ impl Commands {
             .unwrap()
             .ok_or_else(|| format!("Collection '{}' not found", collection_name))?;
 
+        let mut results = Vec::new();
         if let Some(requests) = &collection.requests {
             for request in requests {
                 let command = ManagerCommands::get_endpoint_command(collection_name, &request.name)
@@ -29,15 +49,53 @@ impl Commands {
 
                 let stdin_input = Vec::new();
                 // Run the request
-                match command.execute_request(false, stdin_input, false).await {
-                    Ok((response, elapsed)) => {
+                let response = command
+                    .execute_request(false, stdin_input, false, format.is_text())
+                    .await;
+                if !format.is_text() {
+                    let result = match response {
+                        Ok(response) => TestResult {
+                            endpoint: request.name.clone(),
+                            method: command.to_string(),
+                            url: Some(response.url),
+                            status: Some(response.status),
+                            elapsed_ms: Some(response.elapsed_ms),
+                            attempts: Some(response.attempts.len() + 1),
+                            error: None,
+                        },
+                        Err(e) => TestResult {
+                            endpoint: request.name.clone(),
+                            method: command.to_string(),
+                            url: None,
+                            status: None,
+                            elapsed_ms: None,
+                            attempts: None,
+                            error: Some(e.to_string()),
+                        },
+                    };
+                    // ndjson results are printed as they come
+                    if format == OutputFormat::Ndjson {
+                        format.print(&result)?;
+                    } else {
+                        results.push(result);
+                    }
+                    continue;
+                }
+                match response {
+                    Ok(response) => {
                         // Print the test result in the same format as print_request_method
+                        let attempts = if response.attempts.is_empty() {
+                            String::new()
+                        } else {
+                            format!(", {} attempts", response.attempts.len() + 1)
+                        };
                         println!(
-                            "[{}] {} - {} ({} ms)\n",
+                            "[{}] {} - {} ({} ms{})\n",
                             command.to_string().bold().bright_yellow(),
                             response.url.bold().bright_white(),
                             RequestCommands::colorize_status(response.status),
-                            elapsed
+                            response.elapsed_ms,
+                            attempts
                         );
                     }
                     Err(e) => {
@@ -46,11 +104,17 @@ impl Commands {
                     }
                 }
             }
-        } else {
+        } else if format.is_text() {
             println!("No requests found in collection '{}'", collection_name);
         }
 
-        println!("All tests completed for collection '{}'", collection_name);
+        match format {
+            OutputFormat::Text => {
+                println!("All tests completed for collection '{}'", collection_name)
+            }
+            OutputFormat::Json => format.print(&results)?,
+            OutputFormat::Ndjson => {}
+        }
         Ok(())
     }
 }
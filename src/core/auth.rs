@@ -0,0 +1,253 @@
+//! Authentication - applies collection and endpoint auth settings to requests
+
+use base64::Engine;
+
+use crate::models::collection::{ApiKeyLocation, Auth};
+
+impl Auth {
+    /// Apply the auth to request headers and query parameters, replacing any
+    /// existing value with the same name.
+    ///
+    /// OAuth2 and JWT need a fresh token, SigV4 and HMAC sign the final request and
+    /// Digest answers the server challenge, so they are applied by [`HttpRequest`](crate::core::http_request::HttpRequest)
+    /// when it is sent.
+    pub fn apply(&self, headers: &mut Vec<(String, String)>, query: &mut Vec<(String, String)>) {
+        match self {
+            Auth::None
+            | Auth::OAuth2(_)
+            | Auth::AwsSigV4(_)
+            | Auth::Hmac(_)
+            | Auth::Jwt(_)
+            | Auth::Digest(_) => {}
+            Auth::Basic { username, password } => {
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, password));
+                set_header(headers, "Authorization", format!("Basic {}", credentials));
+            }
+            Auth::Bearer { token } => {
+                set_header(headers, "Authorization", format!("Bearer {}", token));
+            }
+            Auth::ApiKey {
+                key,
+                value,
+                location: ApiKeyLocation::Header,
+            } => set_header(headers, key, value.clone()),
+            Auth::ApiKey {
+                key,
+                value,
+                location: ApiKeyLocation::Query,
+            } => {
+                query.retain(|(k, _)| k != key);
+                query.push((key.clone(), value.clone()));
+            }
+        }
+    }
+
+    /// The auth values that may contain template expressions
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            Auth::None => vec![],
+            Auth::Basic { username, password } => vec![username, password],
+            Auth::Bearer { token } => vec![token],
+            Auth::ApiKey { value, .. } => vec![value],
+            Auth::OAuth2(oauth) => {
+                let mut values = vec![
+                    oauth.token_url.as_str(),
+                    oauth.client_id.as_str(),
+                    oauth.client_secret.as_str(),
+                ];
+                values.extend(
+                    [
+                        &oauth.scope,
+                        &oauth.username,
+                        &oauth.password,
+                        &oauth.refresh_token,
+                        &oauth.authorize_url,
+                        &oauth.redirect_uri,
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str),
+                );
+                values
+            }
+            Auth::AwsSigV4(aws) => {
+                let mut values = vec![
+                    aws.access_key.as_str(),
+                    aws.secret_key.as_str(),
+                    aws.region.as_str(),
+                    aws.service.as_str(),
+                ];
+                values.extend(aws.session_token.as_deref());
+                values
+            }
+            Auth::Hmac(hmac) => vec![&hmac.secret, &hmac.template],
+            Auth::Jwt(jwt) => vec![&jwt.key, &jwt.claims],
+            Auth::Digest(digest) => vec![&digest.username, &digest.password],
+        }
+    }
+
+    /// Mutable references to the auth values that may contain template expressions
+    pub fn values_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Auth::None => vec![],
+            Auth::Basic { username, password } => vec![username, password],
+            Auth::Bearer { token } => vec![token],
+            Auth::ApiKey { value, .. } => vec![value],
+            Auth::OAuth2(oauth) => {
+                let mut values = vec![
+                    &mut oauth.token_url,
+                    &mut oauth.client_id,
+                    &mut oauth.client_secret,
+                ];
+                values.extend(
+                    [
+                        &mut oauth.scope,
+                        &mut oauth.username,
+                        &mut oauth.password,
+                        &mut oauth.refresh_token,
+                        &mut oauth.authorize_url,
+                        &mut oauth.redirect_uri,
+                    ]
+                    .into_iter()
+                    .flatten(),
+                );
+                values
+            }
+            Auth::AwsSigV4(aws) => {
+                let mut values = vec![
+                    &mut aws.access_key,
+                    &mut aws.secret_key,
+                    &mut aws.region,
+                    &mut aws.service,
+                ];
+                values.extend(aws.session_token.as_mut());
+                values
+            }
+            Auth::Hmac(hmac) => vec![&mut hmac.secret, &mut hmac.template],
+            Auth::Jwt(jwt) => vec![&mut jwt.key, &mut jwt.claims],
+            Auth::Digest(digest) => vec![&mut digest.username, &mut digest.password],
+        }
+    }
+
+    /// The secret auth values, with the names they are prompted for by
+    /// [`with_secret_placeholders`](Self::with_secret_placeholders)
+    fn secrets_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        match self {
+            Auth::None => vec![],
+            Auth::Basic { password, .. } => vec![("password", password)],
+            Auth::Bearer { token } => vec![("token", token)],
+            Auth::ApiKey { value, .. } => vec![("api_key", value)],
+            Auth::OAuth2(oauth) => {
+                let mut secrets = vec![("client_secret", &mut oauth.client_secret)];
+                secrets.extend(oauth.password.as_mut().map(|p| ("password", p)));
+                secrets.extend(oauth.refresh_token.as_mut().map(|t| ("refresh_token", t)));
+                secrets
+            }
+            Auth::AwsSigV4(aws) => {
+                let mut secrets = vec![("secret_key", &mut aws.secret_key)];
+                secrets.extend(aws.session_token.as_mut().map(|t| ("session_token", t)));
+                secrets
+            }
+            Auth::Hmac(hmac) => vec![("hmac_secret", &mut hmac.secret)],
+            Auth::Jwt(jwt) => vec![("jwt_key", &mut jwt.key)],
+            Auth::Digest(digest) => vec![("password", &mut digest.password)],
+        }
+    }
+
+    /// The auth with each secret replaced by a `{{? name | secret}}` placeholder,
+    /// so that it can be printed and is prompted for when used. Secrets that
+    /// already are template expressions are kept.
+    pub fn with_secret_placeholders(&self) -> Auth {
+        let mut auth = self.clone();
+        for (name, secret) in auth.secrets_mut() {
+            if !secret.is_empty() && !secret.contains("{{") {
+                *secret = format!("{{{{? {} | secret}}}}", name);
+            }
+        }
+        auth
+    }
+
+    /// Display the auth with its secrets masked
+    pub fn redacted(&self) -> String {
+        match self {
+            Auth::None => "none".to_string(),
+            Auth::Basic { username, .. } => format!("basic {}:****", username),
+            Auth::Bearer { .. } => "bearer ****".to_string(),
+            Auth::ApiKey { key, location, .. } => format!("apikey {} {}=****", location, key),
+            Auth::OAuth2(oauth) => format!(
+                "oauth2 {} {} client_id={}",
+                oauth.grant, oauth.token_url, oauth.client_id
+            ),
+            Auth::AwsSigV4(aws) => format!(
+                "sigv4 {} {}/{}{}",
+                aws.access_key,
+                aws.region,
+                aws.service,
+                if aws.session_token.is_some() {
+                    " with session token"
+                } else {
+                    ""
+                }
+            ),
+            Auth::Hmac(hmac) => format!(
+                "hmac {} {} template={}",
+                hmac.algorithm, hmac.header, hmac.template
+            ),
+            Auth::Jwt(jwt) => format!(
+                "jwt {:?} ttl={}s claims={}",
+                jwt.algorithm, jwt.ttl, jwt.claims
+            ),
+            Auth::Digest(digest) => format!("digest {}:****", digest.username),
+        }
+    }
+}
+
+/// Set a header, replacing any header with the same name (case-insensitive)
+fn set_header(headers: &mut Vec<(String, String)>, name: &str, value: String) {
+    headers.retain(|(k, _)| !k.eq_ignore_ascii_case(name));
+    headers.push((name.to_string(), value));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_auth() {
+        let mut headers = vec![("authorization".to_string(), "old".to_string())];
+        let mut query = vec![("api_key".to_string(), "old".to_string())];
+
+        "basic:user:pass"
+            .parse::<Auth>()
+            .unwrap()
+            .apply(&mut headers, &mut query);
+        assert_eq!(
+            headers,
+            vec![(
+                "Authorization".to_string(),
+                "Basic dXNlcjpwYXNz".to_string()
+            )]
+        );
+
+        "apikey:query:api_key:123"
+            .parse::<Auth>()
+            .unwrap()
+            .apply(&mut headers, &mut query);
+        assert_eq!(query, vec![("api_key".to_string(), "123".to_string())]);
+
+        let auth = "bearer:secret-token".parse::<Auth>().unwrap();
+        assert_eq!(auth.redacted(), "bearer ****");
+
+        let auth = "basic:user:pass".parse::<Auth>().unwrap();
+        let printed = auth.with_secret_placeholders().to_string();
+        assert_eq!(printed, "basic:user:{{? password | secret}}");
+        assert_eq!(
+            printed.parse::<Auth>().unwrap(),
+            auth.with_secret_placeholders()
+        );
+
+        let auth = "bearer:{{$env TOKEN}}".parse::<Auth>().unwrap();
+        assert_eq!(auth.with_secret_placeholders(), auth);
+    }
+}
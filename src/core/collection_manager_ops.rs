@@ -29,6 +29,7 @@ impl CollectionManager {
                     url: url.to_string(),
                     headers: headers.clone(),
                     requests: None,
+                    ..Default::default()
                 };
                 self.update_add_collection(new_collection).await?;
             }
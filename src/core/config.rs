@@ -0,0 +1,330 @@
+//! User configuration - defaults for every command, read from `config.toml`
+//!
+//! The file lives in `$XDG_CONFIG_HOME/coman`, or `~/.config/coman` when the
+//! variable is not set, and `COMAN_CONFIG` points to another file. Collection,
+//! endpoint and command options override its values.
+//!
+//! Only the `coman` binary reads the file, installing it with
+//! [`Config::install`] at startup. `HttpClient` does not: library users load
+//! it and apply [`Config::settings`] with `HttpClient::with_settings`. There is
+//! no default environment key, as collections have no environments to pick from.
+
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::helper;
+use crate::models::collection::RequestSettings;
+
+/// Name of the configuration file in the coman config directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Keys of the configuration file, with what they set
+pub const CONFIG_KEYS: &[(&str, &str)] = &[
+    (
+        "data_file",
+        "Data file holding the collections, unless COMAN_JSON is set",
+    ),
+    ("timeout", "Timeout of the whole request in seconds"),
+    (
+        "connect_timeout",
+        "Timeout of opening the connection in seconds",
+    ),
+    ("follow_redirects", "Follow redirects: true or false"),
+    ("max_redirects", "Most redirects followed before failing"),
+    ("color", "Colored output: auto, always or never"),
+    ("output", "Default -o output of req and run, like 'lines'"),
+];
+
+static GLOBAL: OnceLock<Config> = OnceLock::new();
+
+/// When to color the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Color when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+/// Defaults of the user configuration file, all optional
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Data file holding the collections, `~` standing for the home directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_file: Option<String>,
+    /// Timeout of the whole request in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f64>,
+    /// Timeout of opening the connection in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<f64>,
+    /// Follow redirects instead of returning them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+    /// Most redirects followed before failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+    /// When to color the output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorMode>,
+    /// Default `-o` output of `req` and `run`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl Config {
+    /// Path of the configuration file
+    pub fn path() -> PathBuf {
+        if let Some(path) = env::var_os("COMAN_CONFIG").filter(|path| !path.is_empty()) {
+            return PathBuf::from(path);
+        }
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Path::new(helper::home_dir()).join(".config"))
+            .join("coman")
+            .join(CONFIG_FILE)
+    }
+
+    /// Read the configuration file, the defaults when there is none
+    pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        Self::load_from(&Self::path())
+    }
+
+    /// Read a configuration file, the defaults when it does not exist
+    pub fn load_from(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(format!("Cannot read '{}': {}", path.display(), e).into()),
+        };
+        toml::from_str(&text)
+            .map_err(|e| format!("Invalid config file '{}': {}", path.display(), e).into())
+    }
+
+    /// Write the configuration file, creating its directory
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&Self::path())
+    }
+
+    /// Write a configuration file, creating its directory
+    pub fn save_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        helper::write_to_path(path, toml::to_string(self)?.as_bytes())
+    }
+
+    /// Make this the configuration returned by [`Config::global`]. Only the
+    /// first call has an effect.
+    pub fn install(self) {
+        let _ = GLOBAL.set(self);
+    }
+
+    /// The installed configuration, or the defaults when none was installed
+    pub fn global() -> &'static Config {
+        GLOBAL.get_or_init(Config::default)
+    }
+
+    /// Value of a key, `None` when it is not set
+    pub fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(match Self::check_key(key)? {
+            "data_file" => self.data_file.clone(),
+            "timeout" => self.timeout.map(|seconds| seconds.to_string()),
+            "connect_timeout" => self.connect_timeout.map(|seconds| seconds.to_string()),
+            "follow_redirects" => self.follow_redirects.map(|follow| follow.to_string()),
+            "max_redirects" => self.max_redirects.map(|max| max.to_string()),
+            "color" => self.color.map(|color| color.to_string()),
+            _ => self.output.clone(),
+        })
+    }
+
+    /// Set a key from its text value
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = |expected: &str| format!("Invalid {}: '{}'. Use {}", key, value, expected);
+        let seconds = || match value.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds > 0.0 => Ok(seconds),
+            _ => Err(invalid("a positive number of seconds")),
+        };
+        match Self::check_key(key)? {
+            "data_file" => self.data_file = Some(value.to_string()),
+            "timeout" => self.timeout = Some(seconds()?),
+            "connect_timeout" => self.connect_timeout = Some(seconds()?),
+            "follow_redirects" => {
+                self.follow_redirects = Some(value.parse().map_err(|_| invalid("true or false"))?)
+            }
+            "max_redirects" => {
+                self.max_redirects = Some(value.parse().map_err(|_| invalid("a number"))?)
+            }
+            "color" => self.color = Some(value.parse()?),
+            _ => self.output = Some(value.to_string()),
+        }
+        Ok(())
+    }
+
+    /// Remove a key, going back to the built-in default
+    pub fn unset(&mut self, key: &str) -> Result<(), String> {
+        match Self::check_key(key)? {
+            "data_file" => self.data_file = None,
+            "timeout" => self.timeout = None,
+            "connect_timeout" => self.connect_timeout = None,
+            "follow_redirects" => self.follow_redirects = None,
+            "max_redirects" => self.max_redirects = None,
+            "color" => self.color = None,
+            _ => self.output = None,
+        }
+        Ok(())
+    }
+
+    /// Data file set, with a leading `~/` replaced by the home directory
+    pub fn data_file_path(&self) -> Option<String> {
+        let path = self.data_file.as_deref()?;
+        Some(match path.strip_prefix("~/") {
+            Some(rest) => format!("{}/{}", helper::home_dir(), rest),
+            None => path.to_string(),
+        })
+    }
+
+    /// Timeouts and redirects set, applied before collection settings
+    pub fn settings(&self) -> RequestSettings {
+        let millis = |seconds: f64| (seconds * 1000.0).round().max(1.0) as u64;
+        RequestSettings {
+            timeout_ms: self.timeout.map(millis),
+            connect_timeout_ms: self.connect_timeout.map(millis),
+            follow_redirects: self.follow_redirects,
+            max_redirects: self.max_redirects,
+        }
+    }
+
+    fn check_key(key: &str) -> Result<&'static str, String> {
+        CONFIG_KEYS
+            .iter()
+            .map(|(name, _)| *name)
+            .find(|name| *name == key)
+            .ok_or_else(|| {
+                let keys: Vec<&str> = CONFIG_KEYS.iter().map(|(name, _)| *name).collect();
+                format!("Unknown config key '{}'. Keys: {}", key, keys.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            ColorMode::Auto => "auto",
+            ColorMode::Always => "always",
+            ColorMode::Never => "never",
+        };
+        write!(f, "{}", mode)
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!("Invalid color: '{}'. Use auto, always or never", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_keys() {
+        let mut config = Config::default();
+        config.set("timeout", "2.5").unwrap();
+        config.set("follow_redirects", "true").unwrap();
+        config.set("color", "never").unwrap();
+        config.set("data_file", "~/api/coman.json").unwrap();
+        assert_eq!(config.get("timeout").unwrap().as_deref(), Some("2.5"));
+        assert_eq!(config.get("max_redirects").unwrap(), None);
+        assert!(config.set("timeout", "-1").is_err());
+        assert!(config.set("color", "sometimes").is_err());
+        assert!(config
+            .get("colour")
+            .unwrap_err()
+            .contains("Unknown config key"));
+        assert_eq!(
+            config.data_file_path().unwrap(),
+            format!("{}/api/coman.json", helper::home_dir())
+        );
+        assert_eq!(
+            config.settings(),
+            RequestSettings {
+                timeout_ms: Some(2500),
+                follow_redirects: Some(true),
+                ..Default::default()
+            }
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coman").join(CONFIG_FILE);
+        assert_eq!(Config::load_from(&path).unwrap(), Config::default());
+        config.save_to(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("color = \"never\""), "{}", text);
+        assert_eq!(Config::load_from(&path).unwrap(), config);
+
+        config.unset("color").unwrap();
+        assert_eq!(config.color, None);
+        std::fs::write(&path, "timeout = \"soon\"").unwrap();
+        assert!(Config::load_from(&path)
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid config file"));
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let mut config = Config::default();
+        for key in ["environment", "Timeout", ""] {
+            assert_eq!(
+                config.set(key, "1").unwrap_err(),
+                format!(
+                    "Unknown config key '{}'. Keys: data_file, timeout, connect_timeout, \
+                     follow_redirects, max_redirects, color, output",
+                    key
+                )
+            );
+            assert!(config.unset(key).is_err());
+        }
+        assert_eq!(
+            config.set("max_redirects", "-1").unwrap_err(),
+            "Invalid max_redirects: '-1'. Use a number"
+        );
+        assert!(config.set("follow_redirects", "yes").is_err());
+        assert!(config.set("connect_timeout", "inf").is_err());
+        assert_eq!(config, Config::default());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        for text in [
+            "timeout = ",
+            "[timeout",
+            "timout = 30",
+            "color = \"blue\"",
+            "max_redirects = -1",
+        ] {
+            std::fs::write(&path, text).unwrap();
+            let error = Config::load_from(&path).unwrap_err().to_string();
+            assert!(error.starts_with("Invalid config file"), "{}", error);
+        }
+        // A directory is not a readable file
+        let error = Config::load_from(dir.path()).unwrap_err().to_string();
+        assert!(error.starts_with("Cannot read"), "{}", error);
+    }
+}
@@ -0,0 +1,266 @@
+//! Connection settings - DNS overrides, connection redirects, IP version and local address
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::ClientBuilder;
+use url::Url;
+
+use crate::core::timing::RequestClock;
+use crate::models::collection::{ConnectConfig, ConnectTo, IpVersion, ResolveRule};
+
+impl ConnectConfig {
+    /// Whether no setting differs from the client defaults
+    pub fn is_empty(&self) -> bool {
+        *self == ConnectConfig::default()
+    }
+
+    /// Merge command settings over these ones: command rules are tried first,
+    /// and set options override
+    pub fn merge(&self, other: &ConnectConfig) -> ConnectConfig {
+        ConnectConfig {
+            resolve: other.resolve.iter().chain(&self.resolve).cloned().collect(),
+            connect_to: other
+                .connect_to
+                .iter()
+                .chain(&self.connect_to)
+                .cloned()
+                .collect(),
+            ip_version: other.ip_version.or(self.ip_version),
+            local_address: other.local_address.or(self.local_address),
+        }
+    }
+
+    /// The first connect-to rule matching the host and port of a URL
+    pub fn connect_to_rule(&self, url: &Url) -> Option<&ConnectTo> {
+        let host = url.host_str()?;
+        let port = url.port_or_known_default()?;
+        self.connect_to.iter().find(|rule| {
+            rule.host
+                .as_ref()
+                .is_none_or(|rule_host| same_host(rule_host, host))
+                && rule.port.is_none_or(|rule_port| rule_port == port)
+        })
+    }
+
+    /// Configure a client builder for a request to `url`, installing the
+    /// resolver applying the rules, which records lookups on `clock`
+    pub(crate) fn apply(
+        &self,
+        mut builder: ClientBuilder,
+        url: Option<&Url>,
+        clock: Arc<RequestClock>,
+    ) -> ClientBuilder {
+        if let Some(local_address) = self.local_address {
+            builder = builder.local_address(local_address);
+        }
+
+        let rule = url.and_then(|url| self.connect_to_rule(url));
+        let resolver = ConnectResolver {
+            host: url.and_then(Url::host_str).unwrap_or_default().to_string(),
+            target: rule.and_then(|rule| rule.target_host.clone()),
+            port: rule
+                .and_then(|rule| rule.target_port)
+                .or(url.and_then(Url::port_or_known_default))
+                .unwrap_or_default(),
+            resolve: self.resolve.clone(),
+            ip_version: self.ip_version,
+            clock,
+        };
+        builder.dns_resolver(Arc::new(resolver))
+    }
+
+    /// Point a URL at the port of its connect-to rule, returning the `Host`
+    /// header naming the original authority
+    pub fn redirect_port(&self, url: &mut Url) -> Option<String> {
+        let target_port = self.connect_to_rule(url)?.target_port?;
+        if url.port_or_known_default() == Some(target_port) {
+            return None;
+        }
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str()?, port),
+            None => url.host_str()?.to_string(),
+        };
+        url.set_port(Some(target_port)).ok()?;
+        Some(host)
+    }
+}
+
+/// DNS resolver applying the resolve rules, the connect-to target host of the
+/// request and the IP version
+struct ConnectResolver {
+    host: String,
+    target: Option<String>,
+    port: u16,
+    resolve: Vec<ResolveRule>,
+    ip_version: Option<IpVersion>,
+    clock: Arc<RequestClock>,
+}
+
+impl Resolve for ConnectResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let name = match &self.target {
+            Some(target) if same_host(name.as_str(), &self.host) => target.clone(),
+            _ => name.as_str().to_string(),
+        };
+        let rule = self.resolve.iter().find(|rule| {
+            same_host(&rule.host, &name) && rule.port.is_none_or(|port| port == self.port)
+        });
+        let overridden = rule.map(|rule| rule.addresses.clone());
+        let ip_version = self.ip_version;
+        let clock = self.clock.clone();
+
+        Box::pin(async move {
+            let start = Instant::now();
+            // Port 0 lets the connection use the URL port
+            let addrs: Vec<SocketAddr> = match overridden {
+                Some(addresses) => addresses
+                    .into_iter()
+                    .map(|ip| SocketAddr::new(ip, 0))
+                    .collect(),
+                None => tokio::net::lookup_host((name.as_str(), 0)).await?.collect(),
+            };
+            let addrs: Vec<SocketAddr> = addrs
+                .into_iter()
+                .filter(|addr| is_version(addr.ip(), ip_version))
+                .collect();
+            if addrs.is_empty() {
+                let version = ip_version.map(|v| format!("{} ", v)).unwrap_or_default();
+                return Err(format!("No {}address found for '{}'", version, name).into());
+            }
+            clock.record_dns(start);
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn same_host(a: &str, b: &str) -> bool {
+    a.trim_start_matches('[')
+        .trim_end_matches(']')
+        .eq_ignore_ascii_case(b.trim_start_matches('[').trim_end_matches(']'))
+}
+
+fn is_version(ip: IpAddr, version: Option<IpVersion>) -> bool {
+    match version {
+        Some(IpVersion::Ipv4) => ip.is_ipv4(),
+        Some(IpVersion::Ipv6) => ip.is_ipv6(),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::core::test_server::serve;
+
+    /// Stand-in server answering with the request line and `Host` header
+    fn server() -> u16 {
+        serve(|request, stream| {
+            let body = format!(
+                "{} | host: {}",
+                request.line(),
+                request.header("host").unwrap_or_default()
+            );
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        })
+        .port()
+    }
+
+    async fn get(url: &str, connect: ConnectConfig) -> String {
+        HttpRequest::new(HttpMethod::Get, url)
+            .connect(Some(connect))
+            .send()
+            .await
+            .map(|response| response.body)
+            .unwrap_or_else(|e| e.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_and_connect_to() {
+        let port = server();
+
+        let resolve = ConnectConfig {
+            resolve: vec![format!("api.test:{}:127.0.0.1", port).parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(
+            get(&format!("http://API.test:{}/users", port), resolve.clone()).await,
+            format!("GET /users HTTP/1.1 | host: api.test:{}", port)
+        );
+        // Rules for another port do not apply
+        assert!(get(&format!("http://api.test:{}/", port + 1), resolve)
+            .await
+            .contains("error"));
+
+        let connect_to = ConnectConfig {
+            connect_to: vec![format!("api.test:80:localhost:{}", port).parse().unwrap()],
+            ip_version: Some(IpVersion::Ipv4),
+            ..Default::default()
+        };
+        assert_eq!(
+            get("http://api.test/health", connect_to).await,
+            "GET /health HTTP/1.1 | host: api.test"
+        );
+
+        let ipv6_only = ConnectConfig {
+            resolve: vec!["api.test:*:127.0.0.1".parse().unwrap()],
+            ip_version: Some(IpVersion::Ipv6),
+            ..Default::default()
+        };
+        assert!(get(&format!("http://api.test:{}/", port), ipv6_only)
+            .await
+            .contains("No IPv6 address found for 'api.test'"));
+    }
+
+    #[test]
+    fn test_parse_connect_rules() {
+        let rule: ResolveRule = "example.com:443:10.0.0.1,[2001:db8::1]".parse().unwrap();
+        assert_eq!(rule.port, Some(443));
+        assert_eq!(rule.addresses.len(), 2);
+        assert_eq!(rule.to_string(), "example.com:443:10.0.0.1,[2001:db8::1]");
+        assert_eq!(
+            "example.com:*:10.0.0.1"
+                .parse::<ResolveRule>()
+                .unwrap()
+                .port,
+            None
+        );
+        assert!("example.com:443".parse::<ResolveRule>().is_err());
+        assert!("example.com:443:not-an-ip".parse::<ResolveRule>().is_err());
+
+        let rule: ConnectTo = "example.com::[::1]:8443".parse().unwrap();
+        assert_eq!(rule.host.as_deref(), Some("example.com"));
+        assert_eq!(rule.port, None);
+        assert_eq!(rule.target_host.as_deref(), Some("::1"));
+        assert_eq!(rule.target_port, Some(8443));
+        assert_eq!(
+            "::new.example.com:"
+                .parse::<ConnectTo>()
+                .unwrap()
+                .to_string(),
+            "::new.example.com:"
+        );
+        assert!("a:b:c:d".parse::<ConnectTo>().is_err());
+
+        let mut url = Url::parse("https://example.com/v1").unwrap();
+        let config = ConnectConfig {
+            connect_to: vec![rule],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.redirect_port(&mut url).as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(url.as_str(), "https://example.com:8443/v1");
+    }
+}
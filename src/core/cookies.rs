@@ -0,0 +1,207 @@
+//! Cookie jars - per-collection cookie stores persisted between runs
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use cookie_store::{Cookie, CookieStore};
+use reqwest::header::HeaderValue;
+use url::Url;
+
+use crate::helper;
+
+/// File in the state directory holding the cookies of every collection with a jar
+pub static COOKIES_FILE: &str = "cookies.json";
+
+/// The cookie jar of a collection. Used as the reqwest cookie provider, it stores
+/// `Set-Cookie` responses and sends back the cookies matching each request's domain,
+/// path and scheme until they expire.
+#[derive(Debug)]
+pub struct CookieJar {
+    collection: String,
+    store: Mutex<CookieStore>,
+}
+
+impl CookieJar {
+    /// Load the jar of a collection, without its expired cookies
+    pub fn load(collection: &str) -> Self {
+        let mut jars: HashMap<String, Vec<Cookie<'static>>> = helper::read_state(COOKIES_FILE);
+        let cookies = jars.remove(collection).unwrap_or_default();
+        let store = CookieStore::from_cookies(cookies.into_iter().map(Ok::<_, ()>), false)
+            .unwrap_or_default();
+        Self {
+            collection: collection.to_string(),
+            store: Mutex::new(store),
+        }
+    }
+
+    /// Save the unexpired cookies, including session cookies, so the next run
+    /// continues the same session
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let cookies = self.list();
+        let mut jars: HashMap<String, Vec<Cookie<'static>>> = helper::read_state(COOKIES_FILE);
+        if cookies.is_empty() {
+            if jars.remove(&self.collection).is_none() {
+                return Ok(());
+            }
+        } else {
+            jars.insert(self.collection.clone(), cookies);
+        }
+        helper::write_state(COOKIES_FILE, &jars)
+    }
+
+    /// The unexpired cookies in the jar
+    pub fn list(&self) -> Vec<Cookie<'static>> {
+        self.store().iter_unexpired().cloned().collect()
+    }
+
+    /// Remove the saved cookies of a collection, returning whether there were any
+    pub fn clear(collection: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut jars: HashMap<String, Vec<Cookie<'static>>> = helper::read_state(COOKIES_FILE);
+        if jars.remove(collection).is_none() {
+            return Ok(false);
+        }
+        helper::write_state(COOKIES_FILE, &jars)?;
+        Ok(true)
+    }
+
+    fn store(&self) -> MutexGuard<'_, CookieStore> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let mut store = self.store();
+        for value in cookie_headers.filter_map(|value| value.to_str().ok()) {
+            // Invalid cookies and cookies for another domain are ignored
+            let _ = store.parse(value, url);
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let cookies = self
+            .store()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if cookies.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&cookies).ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::core::test_server::serve;
+    use crate::helper::tests::temp_data_file;
+
+    /// Stand-in server setting cookies on `/login` and recording the `Cookie` header
+    /// of every other request
+    fn cookie_server(cookies: Arc<Mutex<Vec<String>>>) -> String {
+        let addr = serve(move |request, stream| {
+            let headers = if request.line().starts_with("GET /login") {
+                [
+                    "Set-Cookie: session=abc; Path=/; HttpOnly",
+                    "Set-Cookie: scoped=1; Path=/admin; Max-Age=3600",
+                    "Set-Cookie: gone=1; Max-Age=0",
+                    "Set-Cookie: other=1; Domain=example.com",
+                ]
+                .join("\r\n")
+                    + "\r\n"
+            } else if request.line().starts_with("GET /logout") {
+                "Set-Cookie: session=; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT\r\n"
+                    .to_string()
+            } else {
+                let cookie = request.header("cookie").unwrap_or_default();
+                cookies.lock().unwrap().push(cookie.to_string());
+                String::new()
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+                headers
+            )
+            .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    async fn get(base: &str, path: &str) {
+        let jar = Arc::new(CookieJar::load("cookies-test"));
+        HttpRequest::new(HttpMethod::Get, &format!("{}{}", base, path))
+            .cookie_jar(Some(jar))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_cookie_jar_persists_between_requests() {
+        let _data_file = temp_data_file();
+        let cookies = Arc::new(Mutex::new(Vec::new()));
+        let base = cookie_server(cookies.clone());
+
+        get(&base, "/login").await;
+        let mut names: Vec<String> = CookieJar::load("cookies-test")
+            .list()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["scoped", "session"]);
+
+        // Each request loads the jar again, as a new run would
+        get(&base, "/api").await;
+        get(&base, "/admin/users").await;
+        get(&base, "/logout").await;
+        get(&base, "/api").await;
+        let cookies = cookies.lock().unwrap();
+        assert_eq!(cookies[0], "session=abc");
+        let mut admin: Vec<&str> = cookies[1].split("; ").collect();
+        admin.sort();
+        assert_eq!(admin, ["scoped=1", "session=abc"]);
+        assert_eq!(cookies[2], "");
+
+        assert!(CookieJar::clear("cookies-test").unwrap());
+        assert!(CookieJar::load("cookies-test").list().is_empty());
+    }
+
+    #[test]
+    fn test_cookie_jar_secure_cookies() {
+        use reqwest::cookie::CookieStore as _;
+
+        let jar = CookieJar::load("cookies-secure-test");
+        let https = Url::parse("https://api.example.com/login").unwrap();
+        jar.set_cookies(
+            &mut [
+                HeaderValue::from_static("token=1; Secure"),
+                HeaderValue::from_static("lang=en"),
+            ]
+            .iter(),
+            &https,
+        );
+        let mut cookies: Vec<String> = jar
+            .cookies(&https)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .split("; ")
+            .map(String::from)
+            .collect();
+        cookies.sort();
+        assert_eq!(cookies, ["lang=en", "token=1"]);
+        let http = Url::parse("http://api.example.com/").unwrap();
+        assert_eq!(jar.cookies(&http).unwrap(), "lang=en");
+    }
+}
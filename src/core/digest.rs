@@ -0,0 +1,389 @@
+//! HTTP Digest authentication (RFC 7616) - answers the server challenge and caches its
+//! nonce so later requests to the same origin are authenticated up front
+
+use std::collections::HashMap;
+
+use md5::Md5;
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::{Position, Url};
+
+use crate::core::utils::hex;
+use crate::helper;
+use crate::models::collection::DigestAuth;
+
+/// File in the state directory holding the last challenge per origin and user
+pub static DIGEST_NONCES_FILE: &str = "digest_nonces.json";
+
+/// Hash algorithm of a Digest challenge
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DigestAlgorithm {
+    #[serde(rename = "MD5")]
+    Md5,
+    #[serde(rename = "SHA-256")]
+    Sha256,
+}
+
+/// A Digest challenge from a `WWW-Authenticate` header, with the number of
+/// requests already sent with its nonce
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opaque: Option<String>,
+    pub algorithm: DigestAlgorithm,
+    /// Whether the `-sess` variant of the algorithm is used
+    #[serde(default)]
+    pub session: bool,
+    /// Whether the server offered `qop=auth`; legacy RFC 2069 servers don't send a qop
+    #[serde(default)]
+    pub qop: bool,
+    #[serde(default)]
+    pub nc: u32,
+}
+
+impl DigestChallenge {
+    /// Parse a single `WWW-Authenticate` header value. Returns `None` for other
+    /// schemes and for challenges using an unsupported algorithm or qop.
+    pub fn parse(header: &str) -> Option<Self> {
+        let header = header.trim_start();
+        let (scheme, params) = header.split_once(' ').unwrap_or((header, ""));
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+        let params = parse_params(params);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone())
+        };
+
+        let algorithm = param("algorithm").unwrap_or_else(|| "MD5".to_string());
+        let (algorithm, session) = match algorithm.to_uppercase().as_str() {
+            "MD5" => (DigestAlgorithm::Md5, false),
+            "MD5-SESS" => (DigestAlgorithm::Md5, true),
+            "SHA-256" => (DigestAlgorithm::Sha256, false),
+            "SHA-256-SESS" => (DigestAlgorithm::Sha256, true),
+            _ => return None,
+        };
+        let qop = match param("qop") {
+            Some(qop)
+                if qop
+                    .split(',')
+                    .any(|q| q.trim().eq_ignore_ascii_case("auth")) =>
+            {
+                true
+            }
+            Some(_) => return None,
+            None => false,
+        };
+
+        Some(Self {
+            realm: param("realm").unwrap_or_default(),
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            algorithm,
+            session,
+            qop,
+            nc: 0,
+        })
+    }
+
+    /// Pick the strongest supported challenge from `WWW-Authenticate` header values
+    pub fn from_headers<'a>(headers: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        headers
+            .into_iter()
+            .filter_map(Self::parse)
+            .max_by_key(|challenge| challenge.algorithm == DigestAlgorithm::Sha256)
+    }
+
+    /// Build the `Authorization` header value answering this challenge for the
+    /// current nonce count
+    pub fn authorization(
+        &self,
+        auth: &DigestAuth,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        let nc = format!("{:08x}", self.nc);
+        let mut ha1 = self.hash(&format!(
+            "{}:{}:{}",
+            auth.username, self.realm, auth.password
+        ));
+        if self.session {
+            ha1 = self.hash(&format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = self.hash(&format!("{}:{}", method, uri));
+        let response = if self.qop {
+            self.hash(&format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1, self.nonce, nc, cnonce, ha2
+            ))
+        } else {
+            self.hash(&format!("{}:{}:{}", ha1, self.nonce, ha2))
+        };
+
+        let algorithm = match (self.algorithm, self.session) {
+            (DigestAlgorithm::Md5, false) => "MD5",
+            (DigestAlgorithm::Md5, true) => "MD5-sess",
+            (DigestAlgorithm::Sha256, false) => "SHA-256",
+            (DigestAlgorithm::Sha256, true) => "SHA-256-sess",
+        };
+        let mut header = format!(
+            r#"Digest username="{}", realm="{}", uri="{}", algorithm={}, nonce="{}""#,
+            quote(&auth.username),
+            quote(&self.realm),
+            quote(uri),
+            algorithm,
+            quote(&self.nonce)
+        );
+        if self.qop {
+            header.push_str(&format!(r#", nc={}, cnonce="{}", qop=auth"#, nc, cnonce));
+        }
+        header.push_str(&format!(r#", response="{}""#, response));
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+        }
+        header
+    }
+
+    fn hash(&self, data: &str) -> String {
+        match self.algorithm {
+            DigestAlgorithm::Md5 => hex(&Md5::digest(data)),
+            DigestAlgorithm::Sha256 => hex(&Sha256::digest(data)),
+        }
+    }
+}
+
+impl DigestAuth {
+    /// Key of the cached challenge: challenges are reused per origin and user
+    fn cache_key(&self, url: &Url) -> String {
+        format!("{} {}", url.origin().ascii_serialization(), self.username)
+    }
+
+    /// Get the cached challenge for the origin of `url`
+    pub fn cached_challenge(&self, url: &Url) -> Option<DigestChallenge> {
+        let mut challenges: HashMap<String, DigestChallenge> =
+            helper::read_state(DIGEST_NONCES_FILE);
+        challenges.remove(&self.cache_key(url))
+    }
+
+    /// Cache a challenge for the origin of `url`
+    pub fn store_challenge(
+        &self,
+        url: &Url,
+        challenge: &DigestChallenge,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut challenges: HashMap<String, DigestChallenge> =
+            helper::read_state(DIGEST_NONCES_FILE);
+        challenges.insert(self.cache_key(url), challenge.clone());
+        helper::write_state(DIGEST_NONCES_FILE, &challenges)
+    }
+
+    /// Get the `Authorization` header value for a request using the cached
+    /// challenge, if there is one, and count the use of its nonce
+    pub fn authorization(&self, method: &str, url: &Url) -> Option<String> {
+        let mut challenge = self.cached_challenge(url)?;
+        challenge.nc += 1;
+        // A lost nonce count only makes the server send a new challenge
+        let _ = self.store_challenge(url, &challenge);
+        let cnonce = Alphanumeric.sample_string(&mut rand::rng(), 32);
+        let uri = &url[Position::BeforePath..Position::AfterQuery];
+        Some(challenge.authorization(self, method, uri, &cnonce))
+    }
+}
+
+/// Parse comma-separated `name=value` and `name="quoted value"` auth parameters
+fn parse_params(s: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=')).collect();
+        if chars.next().is_none() {
+            break;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            value = std::iter::from_fn(|| chars.next_if(|c| *c != ',')).collect();
+        }
+        params.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    params
+}
+
+/// Escape a value for a quoted auth parameter
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::core::test_server::serve;
+    use crate::helper::tests::temp_data_file;
+    use crate::models::collection::Auth;
+
+    #[test]
+    fn test_digest_rfc7616_example() {
+        // RFC 7616, section 3.9.1
+        let headers = [
+            r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+            r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=MD5, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+        ];
+        let auth = DigestAuth {
+            username: "Mufasa".to_string(),
+            password: "Circle of Life".to_string(),
+        };
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+        let mut challenge = DigestChallenge::from_headers(headers).unwrap();
+        assert_eq!(challenge.algorithm, DigestAlgorithm::Sha256);
+        assert!(challenge.qop);
+        challenge.nc = 1;
+        assert_eq!(
+            challenge.authorization(&auth, "GET", "/dir/index.html", cnonce),
+            r#"Digest username="Mufasa", realm="http-auth@example.org", uri="/dir/index.html", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", nc=00000001, cnonce="f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ", qop=auth, response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#
+        );
+
+        let mut challenge = DigestChallenge::parse(headers[1]).unwrap();
+        challenge.nc = 1;
+        assert!(challenge
+            .authorization(&auth, "GET", "/dir/index.html", cnonce)
+            .contains(r#"response="8ca523f5e9506fed4657c9700eebdbec""#));
+
+        assert!(DigestChallenge::parse(r#"Basic realm="x""#).is_none());
+        assert!(DigestChallenge::parse(r#"Digest realm="x", nonce="n", qop="auth-int""#).is_none());
+    }
+
+    /// Stand-in server requiring Digest auth on every path and rotating its nonce
+    /// after two requests
+    fn digest_server(requests: Arc<Mutex<Vec<String>>>) -> String {
+        let mut nonce = 1;
+        let mut uses = 0;
+        let addr = serve(move |request, stream| {
+            let authorized = request
+                .header("authorization")
+                .filter(|value| value.contains(&format!(r#"nonce="n{}""#, nonce)));
+            let status = match authorized {
+                Some(value) if uses < 2 => {
+                    let challenge = DigestChallenge {
+                        realm: "device".to_string(),
+                        nonce: format!("n{}", nonce),
+                        opaque: None,
+                        algorithm: DigestAlgorithm::Md5,
+                        session: false,
+                        qop: true,
+                        nc: 0,
+                    };
+                    let nc = value.split("nc=").nth(1).unwrap()[..8].to_string();
+                    let cnonce = value.split(r#"cnonce=""#).nth(1).unwrap();
+                    let cnonce = &cnonce[..cnonce.find('"').unwrap()];
+                    let expected = DigestChallenge {
+                        nc: u32::from_str_radix(&nc, 16).unwrap(),
+                        ..challenge
+                    }
+                    .authorization(
+                        &DigestAuth {
+                            username: "admin".to_string(),
+                            password: "secret".to_string(),
+                        },
+                        request.line().split(' ').next().unwrap(),
+                        request.path(),
+                        cnonce,
+                    );
+                    uses += 1;
+                    if value == expected {
+                        "200 OK".to_string()
+                    } else {
+                        "403 Forbidden".to_string()
+                    }
+                }
+                _ => {
+                    if uses > 0 {
+                        nonce += 1;
+                        uses = 0;
+                    }
+                    format!(
+                            "401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"device\", qop=\"auth\", nonce=\"n{}\"{}",
+                            nonce,
+                            if nonce > 1 { ", stale=true" } else { "" }
+                        )
+                }
+            };
+            requests.lock().unwrap().push(request.text());
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_digest_challenge_and_nonce_cache() {
+        let _data_file = temp_data_file();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let base = digest_server(requests.clone());
+        let digest = DigestAuth {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+        };
+        let url = Url::parse(&base).unwrap();
+        let request = HttpRequest::new(HttpMethod::Get, &format!("{}/status", base))
+            .query_param("id", "1")
+            .auth(Some(Auth::Digest(digest.clone())));
+
+        // Challenged, then answered
+        assert_eq!(request.clone().send().await.unwrap().status, 200);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        // Cached nonce is reused with the next nonce count
+        assert_eq!(request.clone().send().await.unwrap().status, 200);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+        assert!(requests.lock().unwrap()[2].contains("nc=00000002"));
+        assert_eq!(digest.cached_challenge(&url).unwrap().nc, 2);
+
+        // Stale nonce is replaced by the new challenge
+        assert_eq!(request.send().await.unwrap().status, 200);
+        assert_eq!(requests.lock().unwrap().len(), 5);
+        let challenge = digest.cached_challenge(&url).unwrap();
+        assert_eq!((challenge.nonce.as_str(), challenge.nc), ("n2", 1));
+
+        // Challenged without a cached nonce, an upload is sent again with the answer
+        let _data_file = temp_data_file();
+        let sent = requests.lock().unwrap().len();
+        let response = HttpRequest::new(HttpMethod::Post, &format!("{}/upload", base))
+            .upload(vec![0x89, b'P', b'N', b'G'], "file.png", "image/png")
+            .auth(Some(Auth::Digest(digest.clone())))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), sent + 2);
+        assert!(requests[sent + 1].contains("filename=\"file.png\""));
+    }
+}
@@ -1,10 +1,13 @@
 use crate::core::collection_manager::CollectionResult;
 use crate::core::errors::CollectionError;
-use crate::core::utils::merge_headers;
+use crate::core::utils::{build_url, fill_path_params, join_url, merge_headers, merge_params};
 use crate::{CollectionManager, Method, Request};
 
 impl CollectionManager {
-    /// Get the full URL for an endpoint (base URL + endpoint path)
+    /// Get the full URL for an endpoint (base URL + endpoint path + encoded query)
+    ///
+    /// Path parameters are filled from the endpoint defaults; parameters without
+    /// a default are left unfilled.
     pub async fn get_endpoint_url(
         &self,
         col_name: &str,
@@ -12,7 +15,11 @@ impl CollectionManager {
     ) -> CollectionResult<String> {
         if let Some(col) = self.get_collection(col_name).await? {
             if let Some(ep) = self.get_endpoint(col_name, ep_name).await? {
-                return Ok(format!("{}{}", col.url, ep.endpoint));
+                let url = join_url(&col.url, &ep.endpoint);
+                let (url, _) = fill_path_params(&url, &ep.params);
+                let query = merge_params(&col.query, &ep.query);
+                return build_url(&url, &query)
+                    .map_err(|e| CollectionError::Other(format!("Invalid URL '{}': {}", url, e)));
             }
         }
         Err(CollectionError::EndpointNotFound(format!(
@@ -37,6 +44,23 @@ impl CollectionManager {
         headers
     }
 
+    /// Get merged query parameters for an endpoint (collection defaults + endpoint query)
+    pub async fn get_endpoint_query(
+        &self,
+        collection: &str,
+        endpoint: &str,
+    ) -> CollectionResult<Vec<(String, String)>> {
+        let col = self
+            .get_collection(collection)
+            .await?
+            .ok_or_else(|| CollectionError::CollectionNotFound(collection.to_string()))?;
+        let ep = self
+            .get_endpoint(collection, endpoint)
+            .await?
+            .ok_or_else(|| CollectionError::EndpointNotFound(endpoint.to_string()))?;
+        Ok(merge_params(&col.query, &ep.query))
+    }
+
     /// Add an endpoint to a collection
     ///
     /// If an endpoint with the same name exists, it will be updated.
@@ -55,6 +79,7 @@ impl CollectionManager {
             method,
             headers,
             body,
+            ..Default::default()
         };
 
         self.update_add_request(col_name, ep_name, request).await?;
@@ -60,6 +60,11 @@ pub enum HttpError {
     RequestError(String),
     /// Response error
     ResponseError(String),
+    /// Error of the last attempt of a retried request
+    RetriesExhausted {
+        attempts: u32,
+        error: Box<HttpError>,
+    },
     /// Generic error
     Other(String),
 }
@@ -72,6 +77,9 @@ impl std::fmt::Display for HttpError {
             HttpError::RedirectError(msg) => write!(f, "Redirect error: {}", msg),
             HttpError::RequestError(msg) => write!(f, "Request error: {}", msg),
             HttpError::ResponseError(msg) => write!(f, "Response error: {}", msg),
+            HttpError::RetriesExhausted { attempts, error } => {
+                write!(f, "{} (after {} attempts)", error, attempts)
+            }
             HttpError::Other(msg) => write!(f, "{}", msg),
         }
     }
@@ -81,14 +89,28 @@ impl std::error::Error for HttpError {}
 
 impl From<reqwest::Error> for HttpError {
     fn from(err: reqwest::Error) -> Self {
+        // The cause, such as a TLS verification failure, is only in the sources
+        let mut message = err.to_string();
+        let mut source = std::error::Error::source(&err);
+        while let Some(cause) = source {
+            message = format!("{}: {}", message, cause);
+            source = cause.source();
+        }
+
         if err.is_timeout() {
             HttpError::Timeout
         } else if err.is_connect() {
-            HttpError::ConnectionError(err.to_string())
+            HttpError::ConnectionError(message)
         } else if err.is_redirect() {
-            HttpError::RedirectError(err.to_string())
+            HttpError::RedirectError(message)
         } else {
-            HttpError::Other(err.to_string())
+            HttpError::Other(message)
         }
     }
 }
+
+impl From<std::io::Error> for HttpError {
+    fn from(err: std::io::Error) -> Self {
+        HttpError::Other(err.to_string())
+    }
+}
@@ -3,10 +3,20 @@
 //! This module provides a clean, library-friendly HTTP client API
 //! without any CLI dependencies (no progress bars, colored output, etc.)
 
+use crate::core::cookies::CookieJar;
 use crate::core::errors::HttpError;
 use crate::core::http_request::HttpRequest;
 use crate::core::http_response::HttpResponse;
+use crate::core::template::{render_generators, render_pairs};
+use crate::core::trace::Trace;
+use crate::core::utils::{join_url, merge_params};
+use crate::models::collection::{
+    ConnectConfig, ConnectTo, FormPart, HttpProtocol, IpVersion, ProxyConfig, RequestSettings,
+    ResolveRule, RetryPolicy, TlsConfig, TlsVersion,
+};
 use crate::CollectionManager;
+use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// HTTP methods supported by the client
@@ -50,8 +60,14 @@ pub type HttpResult<T> = Result<T, HttpError>;
 #[derive(Debug, Clone, Default)]
 pub struct HttpClient {
     default_headers: Vec<(String, String)>,
-    timeout: Option<Duration>,
-    follow_redirects: bool,
+    settings: RequestSettings,
+    tls: TlsConfig,
+    proxy: Option<ProxyConfig>,
+    unix_socket: Option<String>,
+    connect: ConnectConfig,
+    protocol: Option<HttpProtocol>,
+    trace: Option<Arc<Trace>>,
+    retry: Option<RetryPolicy>,
 }
 
 impl HttpClient {
@@ -68,13 +84,135 @@ impl HttpClient {
 
     /// Set default timeout for all requests
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = Some(timeout);
+        self.settings.timeout_ms = Some(timeout.as_millis() as u64);
+        self
+    }
+
+    /// Set the timeout of opening connections for all requests
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.connect_timeout_ms = Some(timeout.as_millis() as u64);
         self
     }
 
     /// Enable following redirects by default
     pub fn with_follow_redirects(mut self, follow: bool) -> Self {
-        self.follow_redirects = follow;
+        self.settings.follow_redirects = Some(follow);
+        self
+    }
+
+    /// Set the most redirects followed
+    pub fn with_max_redirects(mut self, max: usize) -> Self {
+        self.settings.max_redirects = Some(max);
+        self
+    }
+
+    /// Set the timeouts and redirects of all requests, overriding collection
+    /// and endpoint settings
+    pub fn with_settings(mut self, settings: &RequestSettings) -> Self {
+        self.settings = self.settings.merge(settings);
+        self
+    }
+
+    /// Set the TLS settings for all requests, overriding collection settings
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Trust the CA certificates of a PEM file, besides the system roots
+    pub fn with_ca_cert(mut self, path: &str) -> Self {
+        self.tls.ca_cert = Some(path.to_string());
+        self
+    }
+
+    /// Skip certificate and host name verification
+    pub fn with_insecure(mut self, insecure: bool) -> Self {
+        self.tls.insecure = insecure;
+        self
+    }
+
+    /// Authenticate with a PEM client certificate and its private key
+    pub fn with_client_cert(mut self, cert_path: &str, key_path: &str) -> Self {
+        self.tls.client_cert = Some(cert_path.to_string());
+        self.tls.client_key = Some(key_path.to_string());
+        self.tls.client_cert_password = None;
+        self
+    }
+
+    /// Authenticate with a PKCS#12 client certificate
+    pub fn with_client_pkcs12(mut self, path: &str, password: &str) -> Self {
+        self.tls.client_cert = Some(path.to_string());
+        self.tls.client_key = None;
+        self.tls.client_cert_password = Some(password.to_string());
+        self
+    }
+
+    /// Set the lowest TLS version accepted
+    pub fn with_min_tls_version(mut self, version: TlsVersion) -> Self {
+        self.tls.min_version = Some(version);
+        self
+    }
+
+    /// Send all requests through a proxy, overriding collection proxies and the
+    /// `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Send all requests over a Unix domain socket, overriding collection sockets
+    pub fn with_unix_socket(mut self, path: &str) -> Self {
+        self.unix_socket = Some(path.to_string());
+        self
+    }
+
+    /// Set the connection settings, tried before collection settings
+    pub fn with_connect(mut self, connect: ConnectConfig) -> Self {
+        self.connect = connect;
+        self
+    }
+
+    /// Resolve a host to fixed addresses, like curl's `--resolve`
+    pub fn with_resolve(mut self, rule: ResolveRule) -> Self {
+        self.connect.resolve.push(rule);
+        self
+    }
+
+    /// Connect to another host or port, like curl's `--connect-to`. The `Host`
+    /// header and TLS server name keep the URL host.
+    pub fn with_connect_to(mut self, rule: ConnectTo) -> Self {
+        self.connect.connect_to.push(rule);
+        self
+    }
+
+    /// Only connect over IPv4 or IPv6
+    pub fn with_ip_version(mut self, version: IpVersion) -> Self {
+        self.connect.ip_version = Some(version);
+        self
+    }
+
+    /// Bind connections to a local address
+    pub fn with_local_address(mut self, address: IpAddr) -> Self {
+        self.connect.local_address = Some(address);
+        self
+    }
+
+    /// Make all requests with an HTTP version, overriding collection settings:
+    /// HTTP/1.1 only, or HTTP/2 with prior knowledge
+    pub fn with_protocol(mut self, protocol: HttpProtocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Write what goes over the wire for all requests to a trace
+    pub fn with_trace(mut self, trace: Arc<Trace>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Retry failed requests, overriding the endpoint retry policies
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
         self
     }
 
@@ -105,15 +243,16 @@ impl HttpClient {
 
     /// Create a request with a specific method
     pub fn request(&self, method: HttpMethod, url: &str) -> HttpRequest {
-        let mut request = HttpRequest::new(method, url)
+        HttpRequest::new(method, url)
             .headers(self.default_headers.clone())
-            .follow_redirects(self.follow_redirects);
-
-        if let Some(timeout) = self.timeout {
-            request = request.timeout(timeout);
-        }
-
-        request
+            .settings(&self.settings)
+            .tls((!self.tls.is_empty()).then(|| self.tls.clone()))
+            .proxy(self.proxy.clone())
+            .unix_socket(self.unix_socket.clone())
+            .connect((!self.connect.is_empty()).then(|| self.connect.clone()))
+            .protocol(self.protocol)
+            .trace(self.trace.clone())
+            .retry(self.retry.clone())
     }
 
     /// Execute a request from a collection endpoint
@@ -136,21 +275,83 @@ impl HttpClient {
             ))
         })?;
 
-        let url = format!("{}{}", col.url, req.endpoint);
-        let headers = req.headers;
+        let render = |text: &str| render_generators(text).map_err(HttpError::RequestError);
+        let render_all =
+            |pairs: &[(String, String)]| render_pairs(pairs).map_err(HttpError::RequestError);
 
-        let method: HttpMethod = req.method.into();
+        let url = render(&join_url(&col.url, &req.endpoint))?;
+        let headers = render_all(&req.headers)?;
+        let query = render_all(&merge_params(&col.query, &req.query))?;
+        let params = render_all(&req.params)?;
 
-        let mut request = HttpRequest::new(method, &url)
-            .headers(headers)
-            .follow_redirects(self.follow_redirects);
+        let mut auth = col.request_auth(&req);
+        if let Some(auth) = auth.as_mut() {
+            for value in auth.values_mut() {
+                *value = render(value)?;
+            }
+        }
 
-        if let Some(body) = &req.body {
-            request = request.body(body);
+        let mut tls = col.tls.clone().unwrap_or_default().merge(&self.tls);
+        if let Some(password) = tls.client_cert_password.as_mut() {
+            *password = render(password)?;
+        }
+        let mut proxy = self.proxy.clone().or(col.proxy.clone());
+        if let Some(proxy) = proxy.as_mut() {
+            proxy.url = render(&proxy.url)?;
+            if let Some(password) = proxy.password.as_mut() {
+                *password = render(password)?;
+            }
         }
+        let connect = col.connect.clone().unwrap_or_default().merge(&self.connect);
+        let settings = col
+            .settings
+            .clone()
+            .unwrap_or_default()
+            .merge(&req.settings.clone().unwrap_or_default())
+            .merge(&self.settings);
+        let method: HttpMethod = req.method.into();
 
-        if let Some(timeout) = self.timeout {
-            request = request.timeout(timeout);
+        let mut request = HttpRequest::new(method, &url)
+            .headers(headers)
+            .query(query)
+            .path_params(params)
+            .auth(auth)
+            .cookie_jar(col.cookies.then(|| Arc::new(CookieJar::load(&col.name))))
+            .tls((!tls.is_empty()).then_some(tls))
+            .proxy(proxy)
+            .unix_socket(self.unix_socket.clone().or(col.socket.clone()))
+            .connect((!connect.is_empty()).then_some(connect))
+            .protocol(self.protocol.or(col.protocol))
+            .trace(self.trace.clone())
+            .retry(self.retry.clone().or(req.retry.clone()))
+            .settings(&settings);
+
+        if let Some(parts) = req.multipart {
+            let parts = parts
+                .into_iter()
+                .map(|part| match part {
+                    FormPart::Text { name, value } => Ok(FormPart::Text {
+                        name,
+                        value: render(&value)?,
+                    }),
+                    FormPart::File {
+                        name,
+                        path,
+                        content_type,
+                        filename,
+                    } => Ok(FormPart::File {
+                        name,
+                        path: render(&path)?,
+                        content_type,
+                        filename,
+                    }),
+                })
+                .collect::<HttpResult<Vec<_>>>()?;
+            request = request.multipart(parts);
+        } else if let Some(pairs) = req.urlencoded {
+            request = request.form(render_all(&pairs)?);
+        } else if let Some(body) = &req.body {
+            request = request.body(&render(body)?);
         }
 
         request.send().await
@@ -160,10 +361,99 @@ impl HttpClient {
 #[cfg(test)]
 mod tests {
 
+    use crate::core::http_response::HttpVersion;
+    use crate::core::test_server::serve;
+    #[cfg(unix)]
+    use crate::core::test_server::serve_unix;
     use crate::core::utils::build_header_map;
 
     use super::*;
 
+    /// Stand-in server on a Unix socket, answering with the request line and `Host` header
+    #[cfg(unix)]
+    fn unix_server(path: &std::path::Path) {
+        serve_unix(path, |request, stream| {
+            let body = format!(
+                "{} | host: {}",
+                request.line(),
+                request.header("host").unwrap_or_default()
+            );
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let path = std::env::temp_dir().join(format!("coman-test-{}.sock", std::process::id()));
+        unix_server(&path);
+        let socket = path.to_string_lossy().to_string();
+
+        let response = HttpClient::new()
+            .get(&format!("unix://{}:/v1/users", socket))
+            .query(vec![("page".to_string(), "2".to_string())])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.body,
+            "GET /v1/users?page=2 HTTP/1.1 | host: localhost"
+        );
+
+        let response = HttpClient::new()
+            .with_unix_socket(&socket)
+            .get("http://api.local/health")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.body, "GET /health HTTP/1.1 | host: api.local");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_http_protocol() {
+        use std::sync::{Arc, Mutex};
+
+        // Stand-in server recording the first line of each connection
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let lines = received.clone();
+        let addr = serve(move |request, stream| {
+            lines.lock().unwrap().push(request.line().to_string());
+            let _ = write!(
+                stream,
+                "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n"
+            );
+        });
+        let url = format!("http://{}/", addr);
+
+        let response = HttpClient::new()
+            .with_protocol(HttpProtocol::Http1_1)
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.version, HttpVersion::Http11);
+        assert_eq!(response.version.to_string(), "HTTP/1.1");
+
+        // The HTTP/2 connection preface is sent without an upgrade
+        let response = HttpClient::new()
+            .with_protocol(HttpProtocol::Http2PriorKnowledge)
+            .get(&url)
+            .send()
+            .await;
+        assert!(response.is_err());
+        assert_eq!(
+            *received.lock().unwrap(),
+            ["GET / HTTP/1.1", "PRI * HTTP/2.0"]
+        );
+    }
+
     #[test]
     fn test_http_method_display() {
         assert_eq!(HttpMethod::Get.to_string(), "GET");
@@ -176,13 +466,16 @@ mod tests {
     #[test]
     fn test_http_response_status_checks() {
         let response = HttpResponse {
-            version: "HTTP/1.1".to_string(),
+            version: HttpVersion::Http11,
             status: 200,
             status_text: "OK".to_string(),
             headers: Vec::new(),
             body: String::new(),
             elapsed_ms: 0,
+            timings: Default::default(),
             url: String::new(),
+            attempts: Vec::new(),
+            redirects: Vec::new(),
         };
 
         assert!(response.is_success());
@@ -1,14 +1,48 @@
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use futures::StreamExt;
 use reqwest::multipart::Part;
-use reqwest::redirect::Policy;
-use reqwest::{multipart, ClientBuilder};
+use reqwest::{multipart, Client, ClientBuilder, RequestBuilder};
+use url::Url;
 
+use crate::core::cookies::CookieJar;
+use crate::core::digest::DigestChallenge;
 use crate::core::errors::HttpError;
 use crate::core::http_client::{HttpMethod, HttpResult};
-use crate::core::http_response::HttpResponse;
-use crate::core::utils::build_header_map;
+use crate::core::http_response::{Attempt, HttpResponse, Redirect};
+use crate::core::redirect::redirect_policy;
+use crate::core::retry::retry_after;
+use crate::core::sigv4;
+use crate::core::timing::{ConnectTiming, RequestClock};
+use crate::core::trace::Trace;
+use crate::core::utils::{
+    build_header_map, build_url, encode_form, fill_path_params, split_unix_url, unix_time,
+};
+use crate::models::collection::{
+    Auth, ConnectConfig, FormPart, HttpProtocol, ProxyConfig, RequestSettings, RetryPolicy,
+    TlsConfig,
+};
+
+/// Key-value pairs such as headers or query parameters
+type Pairs = Vec<(String, String)>;
+
+/// Response of a sent request, with the clock of its attempt, the redirects
+/// it followed and the failed attempts before it
+struct Sent {
+    response: reqwest::Response,
+    clock: Arc<RequestClock>,
+    redirects: Vec<Redirect>,
+    attempts: Vec<Attempt>,
+}
+
+/// A file sent from memory as the `file` part of a multipart form
+#[derive(Debug, Clone)]
+struct Upload {
+    bytes: Vec<u8>,
+    file_name: String,
+    mime_type: String,
+}
 
 /// HTTP Request Builder
 #[derive(Debug, Clone)]
@@ -16,23 +50,60 @@ pub struct HttpRequest {
     url: String,
     method: HttpMethod,
     headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    path_params: Vec<(String, String)>,
     body: Option<String>,
     body_bytes: Option<Vec<u8>>,
+    multipart: Option<Vec<FormPart>>,
+    upload: Option<Upload>,
+    form: Option<Vec<(String, String)>>,
+    auth: Option<Auth>,
+    cookie_jar: Option<Arc<CookieJar>>,
+    tls: Option<TlsConfig>,
+    proxy: Option<ProxyConfig>,
+    unix_socket: Option<String>,
+    connect: Option<ConnectConfig>,
+    protocol: Option<HttpProtocol>,
+    trace: Option<Arc<Trace>>,
+    retry: Option<RetryPolicy>,
     timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
     follow_redirects: bool,
+    max_redirects: Option<usize>,
 }
 
 impl HttpRequest {
-    /// Create a new HTTP request
+    /// Create a new HTTP request. A `unix:///path/to/app.sock:/path` URL sends
+    /// the request to `/path` over the Unix domain socket.
     pub fn new(method: HttpMethod, url: &str) -> Self {
+        let (unix_socket, url) = match split_unix_url(url) {
+            Some((socket, url)) => (Some(socket), url),
+            None => (None, url.to_string()),
+        };
         Self {
-            url: url.to_string(),
+            url,
             method,
             headers: Vec::new(),
+            query: Vec::new(),
+            path_params: Vec::new(),
             body: None,
             body_bytes: None,
+            multipart: None,
+            upload: None,
+            form: None,
+            auth: None,
+            cookie_jar: None,
+            tls: None,
+            proxy: None,
+            unix_socket,
+            connect: None,
+            protocol: None,
+            trace: None,
+            retry: None,
             timeout: None,
+            connect_timeout: None,
             follow_redirects: false,
+            max_redirects: None,
         }
     }
 
@@ -48,6 +119,30 @@ impl HttpRequest {
         self
     }
 
+    /// Set query parameters, appended percent-encoded to the URL
+    pub fn query(mut self, query: Vec<(String, String)>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Add a single query parameter
+    pub fn query_param(mut self, key: &str, value: &str) -> Self {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set values for `{name}` path parameters in the URL
+    pub fn path_params(mut self, params: Vec<(String, String)>) -> Self {
+        self.path_params = params;
+        self
+    }
+
+    /// Set the value of a single `{name}` path parameter
+    pub fn path_param(mut self, name: &str, value: &str) -> Self {
+        self.path_params.push((name.to_string(), value.to_string()));
+        self
+    }
+
     /// Set request body as string
     pub fn body(mut self, body: &str) -> Self {
         self.body = Some(body.to_string());
@@ -60,27 +155,140 @@ impl HttpRequest {
         self
     }
 
+    /// Set a multipart/form-data body, taking precedence over any other body
+    pub fn multipart(mut self, parts: Vec<FormPart>) -> Self {
+        self.multipart = Some(parts);
+        self
+    }
+
+    /// Set a multipart/form-data body holding `bytes` as its `file` part, named
+    /// `file_name` and typed `mime_type`
+    pub fn upload(mut self, bytes: Vec<u8>, file_name: &str, mime_type: &str) -> Self {
+        self.upload = Some(Upload {
+            bytes,
+            file_name: file_name.to_string(),
+            mime_type: mime_type.to_string(),
+        });
+        self
+    }
+
+    /// Set an `application/x-www-form-urlencoded` body from key-value pairs
+    pub fn form(mut self, pairs: Vec<(String, String)>) -> Self {
+        self.form = Some(pairs);
+        self
+    }
+
+    /// Set the authentication, applied to the headers or query when the request is sent
+    pub fn auth(mut self, auth: Option<Auth>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Set the cookie jar sending and storing the request cookies, saved after the response
+    pub fn cookie_jar(mut self, cookie_jar: Option<Arc<CookieJar>>) -> Self {
+        self.cookie_jar = cookie_jar;
+        self
+    }
+
+    /// Set the TLS settings: CA certificates, insecure mode, client certificate
+    /// and minimum version
+    pub fn tls(mut self, tls: Option<TlsConfig>) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Send the request over a Unix domain socket, the URL host only naming the
+    /// `Host` header. `None` keeps the socket of a `unix://` URL.
+    pub fn unix_socket(mut self, path: Option<String>) -> Self {
+        if path.is_some() {
+            self.unix_socket = path;
+        }
+        self
+    }
+
+    /// Set the proxy, replacing the environment proxies
+    pub fn proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Set the connection settings: DNS overrides, connect-to rules, IP version
+    /// and local address
+    pub fn connect(mut self, connect: Option<ConnectConfig>) -> Self {
+        self.connect = connect;
+        self
+    }
+
+    /// Force an HTTP version instead of negotiating it
+    pub fn protocol(mut self, protocol: Option<HttpProtocol>) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Write what goes over the wire to a trace, see [`Trace`]
+    pub fn trace(mut self, trace: Option<Arc<Trace>>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Retry failed attempts as the policy allows
+    pub fn retry(mut self, retry: Option<RetryPolicy>) -> Self {
+        self.retry = retry;
+        self
+    }
+
     /// Set request timeout
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
+    /// Set the timeout of opening the connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
     /// Enable following redirects
     pub fn follow_redirects(mut self, follow: bool) -> Self {
         self.follow_redirects = follow;
         self
     }
 
-    /// Execute the request
-    pub async fn send(self) -> HttpResult<HttpResponse> {
-        let client_builder = ClientBuilder::new();
+    /// Set the most redirects followed, 10 by default
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = Some(max);
+        self
+    }
 
-        let client_builder = if self.follow_redirects {
-            client_builder.redirect(Policy::default())
-        } else {
-            client_builder.redirect(Policy::none())
-        };
+    /// Apply the timeouts and redirect options set in `settings`
+    pub fn settings(mut self, settings: &RequestSettings) -> Self {
+        if let Some(timeout) = settings.timeout_ms {
+            self.timeout = Some(Duration::from_millis(timeout));
+        }
+        if let Some(timeout) = settings.connect_timeout_ms {
+            self.connect_timeout = Some(Duration::from_millis(timeout));
+        }
+        if let Some(follow) = settings.follow_redirects {
+            self.follow_redirects = follow;
+        }
+        if let Some(max) = settings.max_redirects {
+            self.max_redirects = Some(max);
+        }
+        self
+    }
+
+    /// Build the underlying reqwest client for this request
+    fn build_client(
+        &self,
+        clock: &Arc<RequestClock>,
+        redirects: &Arc<Mutex<Vec<Redirect>>>,
+    ) -> HttpResult<Client> {
+        let client_builder = ClientBuilder::new().redirect(redirect_policy(
+            self.follow_redirects,
+            self.max_redirects,
+            redirects.clone(),
+        ));
 
         let client_builder = if let Some(timeout) = self.timeout {
             client_builder.timeout(timeout)
@@ -88,112 +296,385 @@ impl HttpRequest {
             client_builder
         };
 
-        let client = client_builder
-            .build()
-            .map_err(|e| HttpError::RequestError(e.to_string()))?;
+        let client_builder = if let Some(timeout) = self.connect_timeout {
+            client_builder.connect_timeout(timeout)
+        } else {
+            client_builder
+        };
 
-        let header_map = build_header_map(&self.headers);
+        let client_builder = if let Some(cookie_jar) = &self.cookie_jar {
+            client_builder.cookie_provider(cookie_jar.clone())
+        } else {
+            client_builder
+        };
 
-        let method = match self.method {
-            HttpMethod::Get => reqwest::Method::GET,
-            HttpMethod::Post => reqwest::Method::POST,
-            HttpMethod::Put => reqwest::Method::PUT,
-            HttpMethod::Delete => reqwest::Method::DELETE,
-            HttpMethod::Patch => reqwest::Method::PATCH,
+        let client_builder = match self.protocol {
+            Some(HttpProtocol::Http1_1) => client_builder.http1_only(),
+            Some(HttpProtocol::Http2PriorKnowledge) => client_builder.http2_prior_knowledge(),
+            None => client_builder,
         };
 
-        let start = std::time::Instant::now();
+        let client_builder = client_builder.connection_verbose(self.trace.is_some());
 
-        let request_builder = client.request(method, &self.url).headers(header_map);
+        let client_builder = if let Some(tls) = &self.tls {
+            tls.apply(client_builder)?
+        } else {
+            client_builder
+        };
 
-        let request_builder = if let Some(bytes) = self.body_bytes {
-            request_builder.body(bytes)
-        } else if let Some(body) = self.body {
-            request_builder.body(body)
+        let client_builder = if let Some(proxy) = &self.proxy {
+            proxy.apply(client_builder)?
         } else {
-            request_builder
+            client_builder
         };
 
-        let response = request_builder.send().await?;
+        let client_builder = match &self.unix_socket {
+            #[cfg(unix)]
+            Some(path) => client_builder.unix_socket(path.as_str()),
+            #[cfg(not(unix))]
+            Some(path) => {
+                return Err(HttpError::RequestError(format!(
+                    "Unix socket '{}' is not supported on this platform",
+                    path
+                )))
+            }
+            None => client_builder,
+        };
 
-        let elapsed = start.elapsed().as_millis();
-        let status = response.status().as_u16();
-        let status_text = response.status().to_string();
-        let url = response.url().to_string();
-        let version = format!("{:?}", response.version());
+        // Rules match the request URL; an invalid URL fails when the request is built
+        let url = self
+            .request_url(&[])
+            .ok()
+            .and_then(|url| Url::parse(&url).ok());
+        let connect = match &self.connect {
+            Some(connect) if self.unix_socket.is_none() => connect.clone(),
+            _ => ConnectConfig::default(),
+        };
+        let client_builder = connect
+            .apply(client_builder, url.as_ref(), clock.clone())
+            .connector_layer(ConnectTiming(clock.clone()));
 
-        let mut headers = Vec::new();
-        for (key, value) in response.headers().iter() {
-            if let Ok(v) = value.to_str() {
-                headers.push((key.to_string(), v.to_string()));
+        client_builder
+            .build()
+            .map_err(|e| HttpError::RequestError(e.to_string()))
+    }
+
+    /// Get the request headers and query parameters with the auth applied,
+    /// fetching a new OAuth2 token when `refresh_auth` is set
+    async fn auth_parts(&self, client: &Client, refresh_auth: bool) -> HttpResult<(Pairs, Pairs)> {
+        let mut headers = self.headers.clone();
+        let mut query = self.query.clone();
+        match &self.auth {
+            Some(Auth::OAuth2(oauth)) => {
+                let token = oauth.access_token(client, refresh_auth).await?;
+                Auth::Bearer { token }.apply(&mut headers, &mut query);
+            }
+            Some(Auth::Jwt(jwt)) => {
+                let token = jwt.mint(unix_time()).map_err(HttpError::RequestError)?;
+                headers.retain(|(k, _)| !k.eq_ignore_ascii_case(&jwt.header));
+                headers.push((jwt.header.clone(), format!("{}{}", jwt.prefix, token)));
             }
+            Some(auth) => auth.apply(&mut headers, &mut query),
+            None => {}
         }
+        Ok((headers, query))
+    }
 
-        let body_bytes = response.bytes().await?.to_vec();
-        let body = String::from_utf8_lossy(&body_bytes).to_string();
+    /// Check if a request rejected with 401 should be retried with a fresh OAuth2
+    /// token or an answer to the Digest challenge, caching the challenge
+    fn should_refresh_auth(&self, response: &reqwest::Response) -> HttpResult<bool> {
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(false);
+        }
+        match &self.auth {
+            Some(Auth::OAuth2(_)) => Ok(true),
+            Some(Auth::Digest(digest)) => {
+                let challenge = DigestChallenge::from_headers(
+                    response
+                        .headers()
+                        .get_all(reqwest::header::WWW_AUTHENTICATE)
+                        .iter()
+                        .filter_map(|value| value.to_str().ok()),
+                );
+                let Some(challenge) = challenge else {
+                    return Ok(false);
+                };
+                digest
+                    .store_challenge(response.url(), &challenge)
+                    .map_err(|e| {
+                        HttpError::Other(format!("Failed to cache Digest challenge: {}", e))
+                    })?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
 
-        Ok(HttpResponse {
-            version,
-            status,
-            status_text,
-            headers,
-            body,
-            elapsed_ms: elapsed,
-            url,
-        })
+    /// Start building the reqwest request with its method, URL, headers and auth
+    async fn request_builder(
+        &self,
+        client: &Client,
+        refresh_auth: bool,
+    ) -> HttpResult<RequestBuilder> {
+        let (mut headers, query) = self.auth_parts(client, refresh_auth).await?;
+        let url = self.request_url(&query)?;
+
+        match &self.auth {
+            Some(Auth::AwsSigV4(aws)) => {
+                let payload_hash = if self.multipart.is_some() || self.upload.is_some() {
+                    sigv4::UNSIGNED_PAYLOAD.to_string()
+                } else {
+                    sigv4::payload_hash(&self.payload())
+                };
+                let parsed = parse_url(&url)?;
+                headers.retain(|(k, _)| !k.eq_ignore_ascii_case("authorization"));
+                let signed = aws.sign(
+                    self.reqwest_method().as_str(),
+                    &parsed,
+                    &headers,
+                    &payload_hash,
+                    unix_time(),
+                );
+                headers.extend(signed);
+            }
+            Some(Auth::Hmac(hmac)) => {
+                if self.multipart.is_some() {
+                    return Err(HttpError::RequestError(
+                        "HMAC signing does not support multipart forms".to_string(),
+                    ));
+                }
+                let parsed = parse_url(&url)?;
+                headers.retain(|(k, _)| !k.eq_ignore_ascii_case(&hmac.header));
+                let signed = hmac.sign(
+                    self.reqwest_method().as_str(),
+                    &parsed,
+                    &self.payload(),
+                    unix_time(),
+                );
+                headers.extend(signed);
+            }
+            Some(Auth::Digest(digest)) => {
+                let parsed = parse_url(&url)?;
+                if let Some(authorization) =
+                    digest.authorization(self.reqwest_method().as_str(), &parsed)
+                {
+                    headers.retain(|(k, _)| !k.eq_ignore_ascii_case("authorization"));
+                    headers.push(("Authorization".to_string(), authorization));
+                }
+            }
+            _ => {}
+        }
+
+        // A connect-to port moves the URL, the Host header keeping the original one
+        let mut url = parse_url(&url)?;
+        if let Some(host) = self
+            .connect
+            .as_ref()
+            .filter(|_| self.unix_socket.is_none())
+            .and_then(|connect| connect.redirect_port(&mut url))
+        {
+            if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("host")) {
+                headers.push(("Host".to_string(), host));
+            }
+        }
+
+        Ok(client
+            .request(self.reqwest_method(), url)
+            .headers(build_header_map(&headers)))
     }
 
-    /// Execute the request and stream the response
-    pub async fn send_streaming<F>(self, mut on_chunk: F) -> HttpResult<HttpResponse>
-    where
-        F: FnMut(&[u8]) -> Result<(), Box<dyn std::error::Error>> + Send,
-    {
-        let client_builder = ClientBuilder::new();
+    /// Save the cookie jar after a response. Like OAuth2 tokens, cookies that can't
+    /// be saved don't fail the request.
+    fn save_cookies(&self) {
+        if let Some(cookie_jar) = &self.cookie_jar {
+            let _ = cookie_jar.save();
+        }
+    }
 
-        let client_builder = if self.follow_redirects {
-            client_builder.redirect(Policy::default())
+    /// Get the bytes of the request body, except for multipart forms. An upload
+    /// gives the bytes of its file part.
+    fn payload(&self) -> Vec<u8> {
+        if let Some(upload) = &self.upload {
+            upload.bytes.clone()
+        } else if let Some(pairs) = &self.form {
+            encode_form(pairs).into_bytes()
+        } else if let Some(bytes) = &self.body_bytes {
+            bytes.clone()
+        } else if let Some(body) = &self.body {
+            body.clone().into_bytes()
         } else {
-            client_builder.redirect(Policy::none())
-        };
+            Vec::new()
+        }
+    }
 
-        let client_builder = if let Some(timeout) = self.timeout {
-            client_builder.timeout(timeout)
+    /// Add the request body to a request builder
+    fn with_body(&self, request_builder: RequestBuilder) -> HttpResult<RequestBuilder> {
+        Ok(if let Some(parts) = &self.multipart {
+            request_builder.multipart(Self::build_form(parts)?)
+        } else if let Some(upload) = &self.upload {
+            let part = Part::bytes(upload.bytes.clone())
+                .file_name(upload.file_name.clone())
+                .mime_str(&upload.mime_type)
+                .map_err(|e| HttpError::RequestError(e.to_string()))?;
+            request_builder.multipart(multipart::Form::new().part("file", part))
+        } else if let Some(pairs) = &self.form {
+            request_builder
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                )
+                .body(encode_form(pairs))
+        } else if let Some(bytes) = &self.body_bytes {
+            request_builder.body(bytes.clone())
+        } else if let Some(body) = &self.body {
+            request_builder.body(body.clone())
         } else {
-            client_builder
-        };
-
-        let client = client_builder
-            .build()
-            .map_err(|e| HttpError::RequestError(e.to_string()))?;
+            request_builder
+        })
+    }
 
-        let header_map = build_header_map(&self.headers);
+    /// Get the request URL with path parameters filled and query parameters appended
+    fn request_url(&self, query: &[(String, String)]) -> HttpResult<String> {
+        let (url, missing) = fill_path_params(&self.url, &self.path_params);
+        if !missing.is_empty() {
+            return Err(HttpError::RequestError(format!(
+                "Missing path parameters: {}",
+                missing.join(", ")
+            )));
+        }
+        build_url(&url, query)
+            .map_err(|e| HttpError::RequestError(format!("Invalid URL '{}': {}", url, e)))
+    }
 
-        let method = match self.method {
+    fn reqwest_method(&self) -> reqwest::Method {
+        match self.method {
             HttpMethod::Get => reqwest::Method::GET,
             HttpMethod::Post => reqwest::Method::POST,
             HttpMethod::Put => reqwest::Method::PUT,
             HttpMethod::Delete => reqwest::Method::DELETE,
             HttpMethod::Patch => reqwest::Method::PATCH,
-        };
-
-        let start = std::time::Instant::now();
+        }
+    }
 
-        let request_builder = client.request(method, &self.url).headers(header_map);
+    /// Build a multipart form from its parts, reading file parts from disk
+    fn build_form(parts: &[FormPart]) -> HttpResult<multipart::Form> {
+        let mut form = multipart::Form::new();
+        for part in parts {
+            form = match part {
+                FormPart::Text { name, value } => form.text(name.clone(), value.clone()),
+                FormPart::File {
+                    name,
+                    path,
+                    content_type,
+                    filename,
+                } => {
+                    let bytes = std::fs::read(path).map_err(|e| {
+                        HttpError::RequestError(format!("Failed to read file '{}': {}", path, e))
+                    })?;
+
+                    let filename = filename.clone().unwrap_or_else(|| {
+                        std::path::Path::new(path)
+                            .file_name()
+                            .map(|f| f.to_string_lossy().to_string())
+                            .unwrap_or_else(|| path.clone())
+                    });
+
+                    let mime_type = content_type
+                        .clone()
+                        .or_else(|| infer::get(&bytes).map(|k| k.mime_type().to_string()));
+
+                    let file_part = Part::bytes(bytes).file_name(filename);
+                    let file_part = match mime_type {
+                        Some(mime) => file_part
+                            .mime_str(&mime)
+                            .map_err(|e| HttpError::RequestError(e.to_string()))?,
+                        None => file_part,
+                    };
+                    form.part(name.clone(), file_part)
+                }
+            };
+        }
+        Ok(form)
+    }
 
-        let request_builder = if let Some(bytes) = self.body_bytes {
-            request_builder.body(bytes)
-        } else if let Some(body) = self.body {
-            request_builder.body(body)
-        } else {
-            request_builder
-        };
+    /// Send the request once with a client of its own, and again when the auth
+    /// must be refreshed
+    async fn attempt(&self) -> HttpResult<Sent> {
+        let clock = RequestClock::new();
+        let redirects = Arc::new(Mutex::new(Vec::new()));
+        let client = self.build_client(&clock, &redirects)?;
+        clock.start();
+
+        let request_builder = self.request_builder(&client, false).await?;
+        let mut response = self.with_body(request_builder)?.send().await?;
+
+        if self.should_refresh_auth(&response)? {
+            redirects.lock().unwrap().clear();
+            let request_builder = self.request_builder(&client, true).await?;
+            response = self.with_body(request_builder)?.send().await?;
+        }
+        let redirects = redirects.lock().unwrap().clone();
+        Ok(Sent {
+            response,
+            clock,
+            redirects,
+            attempts: Vec::new(),
+        })
+    }
 
-        let response = request_builder.send().await?;
+    /// Send the request, making new attempts as long as the retry policy allows
+    async fn attempts(&self) -> HttpResult<Sent> {
+        let mut attempts = Vec::new();
+        loop {
+            let result = self.attempt().await;
+            let retry = attempts.len() as u32 + 1;
+            let outcome = match (&self.retry, &result) {
+                (Some(policy), _) if retry >= policy.max_attempts => None,
+                (Some(policy), Ok(Sent { response, .. }))
+                    if policy.retries_status(response.status().as_u16()) =>
+                {
+                    let retry_after = retry_after(response.headers(), unix_time());
+                    Some((policy, response.status().to_string(), retry_after))
+                }
+                (Some(policy), Err(error))
+                    if policy.retries_error(error, &self.reqwest_method()) =>
+                {
+                    Some((policy, error.to_string(), None))
+                }
+                _ => None,
+            };
+
+            let Some((policy, outcome, retry_after)) = outcome else {
+                return match result {
+                    Ok(sent) => Ok(Sent { attempts, ..sent }),
+                    Err(error) if attempts.is_empty() => Err(error),
+                    Err(error) => Err(HttpError::RetriesExhausted {
+                        attempts: retry,
+                        error: Box::new(error),
+                    }),
+                };
+            };
+            let delay = policy.delay(retry, retry_after);
+            attempts.push(Attempt { outcome, delay });
+            tokio::time::sleep(delay).await;
+        }
+    }
 
+    /// Execute the request
+    pub async fn send(self) -> HttpResult<HttpResponse> {
+        let _trace = self.trace.as_ref().map(|trace| trace.begin()).transpose()?;
+        let Sent {
+            response,
+            clock,
+            redirects,
+            attempts,
+        } = self.attempts().await?;
+
+        let headers_received = Instant::now();
         let status = response.status().as_u16();
         let status_text = response.status().to_string();
         let url = response.url().to_string();
-        let version = format!("{:?}", response.version());
+        let version = response.version().into();
 
         let mut headers = Vec::new();
         for (key, value) in response.headers().iter() {
@@ -202,71 +683,43 @@ impl HttpRequest {
             }
         }
 
-        let mut stream = response.bytes_stream();
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| HttpError::ResponseError(e.to_string()))?;
-            on_chunk(&chunk).map_err(|e| HttpError::Other(e.to_string()))?;
-        }
-
-        let elapsed = start.elapsed().as_millis();
+        let body_bytes = response.bytes().await?.to_vec();
+        let body = String::from_utf8_lossy(&body_bytes).to_string();
+        self.save_cookies();
+        let timings = clock.timings(headers_received, Instant::now());
 
         Ok(HttpResponse {
             version,
             status,
             status_text,
             headers,
-            body: String::new(),
-            elapsed_ms: elapsed,
+            body,
+            elapsed_ms: timings.total.as_millis(),
+            timings,
             url,
+            attempts,
+            redirects,
         })
     }
 
-    pub async fn send_multipart(self, part: Part) -> HttpResult<HttpResponse> {
-        let client_builder = ClientBuilder::new();
-
-        let client_builder = if self.follow_redirects {
-            client_builder.redirect(Policy::default())
-        } else {
-            client_builder.redirect(Policy::none())
-        };
-
-        let client_builder = if let Some(timeout) = self.timeout {
-            client_builder.timeout(timeout)
-        } else {
-            client_builder
-        };
-
-        let client = client_builder
-            .build()
-            .map_err(|e| HttpError::RequestError(e.to_string()))?;
-
-        let header_map = build_header_map(&self.headers);
-
-        let method = match self.method {
-            HttpMethod::Get => reqwest::Method::GET,
-            HttpMethod::Post => reqwest::Method::POST,
-            HttpMethod::Put => reqwest::Method::PUT,
-            HttpMethod::Delete => reqwest::Method::DELETE,
-            HttpMethod::Patch => reqwest::Method::PATCH,
-        };
-
-        let form = multipart::Form::new().part("file", part);
-
-        let start = std::time::Instant::now();
-
-        let response = client
-            .request(method, &self.url)
-            .headers(header_map)
-            .multipart(form)
-            .send()
-            .await?;
-
-        let elapsed = start.elapsed().as_millis();
+    /// Execute the request and stream the response
+    pub async fn send_streaming<F>(self, mut on_chunk: F) -> HttpResult<HttpResponse>
+    where
+        F: FnMut(&[u8]) -> Result<(), Box<dyn std::error::Error>> + Send,
+    {
+        let _trace = self.trace.as_ref().map(|trace| trace.begin()).transpose()?;
+        let Sent {
+            response,
+            clock,
+            redirects,
+            attempts,
+        } = self.attempts().await?;
+
+        let headers_received = Instant::now();
         let status = response.status().as_u16();
         let status_text = response.status().to_string();
         let url = response.url().to_string();
-        let version = format!("{:?}", response.version());
+        let version = response.version().into();
 
         let mut headers = Vec::new();
         for (key, value) in response.headers().iter() {
@@ -275,17 +728,32 @@ impl HttpRequest {
             }
         }
 
-        let body_bytes = response.bytes().await?.to_vec();
-        let body = String::from_utf8_lossy(&body_bytes).to_string();
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| HttpError::ResponseError(e.to_string()))?;
+            on_chunk(&chunk).map_err(|e| HttpError::Other(e.to_string()))?;
+        }
+
+        self.save_cookies();
+        let timings = clock.timings(headers_received, Instant::now());
 
         Ok(HttpResponse {
             version,
             status,
             status_text,
             headers,
-            body,
-            elapsed_ms: elapsed,
+            body: String::new(),
+            elapsed_ms: timings.total.as_millis(),
+            timings,
             url,
+            attempts,
+            redirects,
         })
     }
 }
+
+/// Parse a built request URL for signing
+fn parse_url(url: &str) -> HttpResult<Url> {
+    Url::parse(url).map_err(|e| HttpError::RequestError(format!("Invalid URL '{}': {}", url, e)))
+}
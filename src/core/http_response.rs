@@ -1,13 +1,24 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::core::errors::HttpError;
+use crate::core::http_client::HttpResult;
+use crate::core::json_path::JsonPath;
+
 /// HTTP Response
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HttpResponse {
     /// HTTP Version
-    pub version: String,
+    pub version: HttpVersion,
     /// Response status code
     pub status: u16,
     /// Response status text
     pub status_text: String,
-    /// Response headers
+    /// Response headers, serialized as `{"name", "value"}` objects
+    #[serde(serialize_with = "serialize_headers")]
     pub headers: Vec<(String, String)>,
     /// Response body as string
     pub body: String,
@@ -15,8 +26,125 @@ pub struct HttpResponse {
     // pub body_bytes: Vec<u8>,
     /// Request duration in milliseconds
     pub elapsed_ms: u128,
+    /// Time spent in each phase of the request
+    pub timings: Timings,
     /// Final URL (after redirects)
     pub url: String,
+    /// Failed attempts retried before this response
+    pub attempts: Vec<Attempt>,
+    /// Redirects followed to this response
+    pub redirects: Vec<Redirect>,
+}
+
+/// Redirect followed on the way to a response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Redirect {
+    /// Redirect status, such as 301 or 302
+    pub status: u16,
+    /// URL answering with the redirect
+    pub url: String,
+    /// URL redirected to
+    pub location: String,
+}
+
+/// Failed attempt of a retried request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attempt {
+    /// Status or error the attempt failed with
+    pub outcome: String,
+    /// Wait before the next attempt
+    #[serde(rename = "delay_ms", serialize_with = "serialize_ms")]
+    pub delay: Duration,
+}
+
+/// Time spent in each phase of a request, serialized in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Timings {
+    /// Host name lookup, unless the URL host is an IP address
+    #[serde(rename = "dns_ms", serialize_with = "serialize_opt_ms")]
+    pub dns: Option<Duration>,
+    /// Opening the connection after the lookup: TCP connect, and the TLS and
+    /// proxy handshakes, which the client does not time apart
+    #[serde(rename = "connect_ms", serialize_with = "serialize_opt_ms")]
+    pub connect: Option<Duration>,
+    /// Waiting for the response headers once connected
+    #[serde(rename = "ttfb_ms", serialize_with = "serialize_ms")]
+    pub ttfb: Duration,
+    /// Reading the response body
+    #[serde(rename = "download_ms", serialize_with = "serialize_ms")]
+    pub download: Duration,
+    /// Whole request
+    #[serde(rename = "total_ms", serialize_with = "serialize_ms")]
+    pub total: Duration,
+}
+
+/// HTTP version of a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpVersion {
+    Http09,
+    Http10,
+    Http11,
+    Http2,
+    Http3,
+}
+
+impl From<reqwest::Version> for HttpVersion {
+    fn from(version: reqwest::Version) -> Self {
+        match version {
+            reqwest::Version::HTTP_09 => HttpVersion::Http09,
+            reqwest::Version::HTTP_10 => HttpVersion::Http10,
+            reqwest::Version::HTTP_2 => HttpVersion::Http2,
+            reqwest::Version::HTTP_3 => HttpVersion::Http3,
+            _ => HttpVersion::Http11,
+        }
+    }
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpVersion::Http09 => write!(f, "HTTP/0.9"),
+            HttpVersion::Http10 => write!(f, "HTTP/1.0"),
+            HttpVersion::Http11 => write!(f, "HTTP/1.1"),
+            HttpVersion::Http2 => write!(f, "HTTP/2"),
+            HttpVersion::Http3 => write!(f, "HTTP/3"),
+        }
+    }
+}
+
+impl Serialize for HttpVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Serialize headers as an array of `{"name", "value"}` objects, keeping their
+/// order and repeated headers
+fn serialize_headers<S: Serializer>(
+    headers: &[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Header<'a> {
+        name: &'a str,
+        value: &'a str,
+    }
+    serializer.collect_seq(headers.iter().map(|(name, value)| Header { name, value }))
+}
+
+/// Serialize a duration as fractional milliseconds
+fn serialize_ms<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+fn serialize_opt_ms<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serialize_ms(duration, serializer),
+        None => serializer.serialize_none(),
+    }
 }
 
 impl HttpResponse {
@@ -44,4 +172,111 @@ impl HttpResponse {
     pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
         serde_json::from_str(&self.body)
     }
+
+    /// Values of the JSON body matched by a JSONPath expression like
+    /// `$.data[?(@.active)].id`, see [`JsonPath`]
+    pub fn query(&self, path: &str) -> HttpResult<Vec<Value>> {
+        let path: JsonPath = path.parse().map_err(HttpError::Other)?;
+        self.query_path(&path)
+    }
+
+    /// Values of the JSON body matched by a parsed path, reusable across responses
+    pub fn query_path(&self, path: &JsonPath) -> HttpResult<Vec<Value>> {
+        let json: Value = self
+            .json()
+            .map_err(|e| HttpError::ResponseError(format!("Response body is not JSON: {}", e)))?;
+        Ok(path.query(&json).into_iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_response_json() {
+        let response = HttpResponse {
+            version: HttpVersion::Http2,
+            status: 200,
+            status_text: "200 OK".to_string(),
+            headers: vec![
+                ("set-cookie".to_string(), "a=1".to_string()),
+                ("set-cookie".to_string(), "b=2".to_string()),
+            ],
+            body: "{}".to_string(),
+            elapsed_ms: 12,
+            timings: Timings {
+                dns: None,
+                connect: Some(Duration::from_micros(1500)),
+                ttfb: Duration::from_millis(8),
+                download: Duration::from_millis(2),
+                total: Duration::from_millis(12),
+            },
+            url: "https://api.example.com/".to_string(),
+            attempts: vec![Attempt {
+                outcome: "503 Service Unavailable".to_string(),
+                delay: Duration::from_millis(250),
+            }],
+            redirects: Vec::new(),
+        };
+
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["version"], "HTTP/2");
+        assert_eq!(
+            value["headers"],
+            json!([
+                {"name": "set-cookie", "value": "a=1"},
+                {"name": "set-cookie", "value": "b=2"}
+            ])
+        );
+        assert_eq!(
+            value["timings"],
+            json!({
+                "dns_ms": null,
+                "connect_ms": 1.5,
+                "ttfb_ms": 8.0,
+                "download_ms": 2.0,
+                "total_ms": 12.0
+            })
+        );
+        assert_eq!(value["attempts"][0]["delay_ms"], 250.0);
+        assert_eq!(value["elapsed_ms"], 12);
+    }
+
+    #[test]
+    fn test_query() {
+        let mut response = HttpResponse {
+            version: HttpVersion::Http11,
+            status: 200,
+            status_text: "200 OK".to_string(),
+            headers: Vec::new(),
+            body: r#"{"data": [{"id": 1, "active": true}, {"id": 2}]}"#.to_string(),
+            elapsed_ms: 0,
+            timings: Timings::default(),
+            url: "http://localhost/".to_string(),
+            attempts: Vec::new(),
+            redirects: Vec::new(),
+        };
+
+        assert_eq!(
+            response.query("$.data[?(@.active)].id").unwrap(),
+            vec![json!(1)]
+        );
+        let path: JsonPath = "data[*].id".parse().unwrap();
+        assert_eq!(
+            response.query_path(&path).unwrap(),
+            vec![json!(1), json!(2)]
+        );
+        assert!(matches!(
+            response.query("$.data[?(@.id ==)]"),
+            Err(HttpError::Other(message)) if message.contains("JSON path")
+        ));
+
+        response.body = "<html></html>".to_string();
+        assert!(matches!(
+            response.query_path(&path),
+            Err(HttpError::ResponseError(message)) if message.contains("not JSON")
+        ));
+    }
 }
@@ -0,0 +1,627 @@
+//! JSONPath expressions - querying parts of a JSON document
+//!
+//! Supported syntax:
+//!
+//! - `$`: the whole document, optional at the start of a path
+//! - `.key` or `['key']`: member of an object
+//! - `[0]`, `[-1]`: element of an array, negative indices counting from the end
+//! - `[0,2]`, `['a','b']`: several members or elements
+//! - `[1:3]`, `[::2]`: array slice with optional start, end and step
+//! - `.*` or `[*]`: all members or elements
+//! - `..key`, `..*`: recursive descent, matching at any depth
+//! - `[?(@.active)]`: members or elements passing a filter, with `==`, `!=`,
+//!   `<`, `<=`, `>`, `>=`, `&&`, `||`, `!` and parentheses. `@` is the
+//!   member tested, `$` the document. A path alone passes when it matches
+//!   a value other than `false` and `null`.
+//!
+//! A path not starting with `$` is a key of the document, so `data.items`
+//! is the same as `$.data.items`.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+/// A parsed JSONPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// Selects in the current values
+    Child(Selector),
+    /// Selects in the current values and all their descendants
+    Descendant(Selector),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Union(Vec<Selector>),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Compare(Operand, CompareOp, Operand),
+    Exists(Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    /// Path from the value tested, `@`
+    Current(Vec<Segment>),
+    /// Path from the document, `$`
+    Root(Vec<Segment>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl JsonPath {
+    /// Values of a document matched by the path, in document order
+    pub fn query<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        select(&self.segments, document, document)
+    }
+
+    /// Whether the path matches at most one value: only keys and indices,
+    /// without wildcards, slices, filters or recursive descent
+    pub fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            matches!(
+                segment,
+                Segment::Child(Selector::Name(_) | Selector::Index(_))
+            )
+        })
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let mut parser = Parser {
+            chars: trimmed.chars().collect(),
+            pos: 0,
+        };
+        // a bare key is a member of the document
+        if !parser.eat('$') && !matches!(parser.peek(), Some('.' | '[') | None) {
+            parser.chars.insert(0, '.');
+        }
+        let segments = parser.segments()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("Unexpected character"));
+        }
+        Ok(JsonPath {
+            source: s.to_string(),
+            segments,
+        })
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn select<'a>(segments: &[Segment], value: &'a Value, root: &'a Value) -> Vec<&'a Value> {
+    let mut values = vec![value];
+    for segment in segments {
+        let mut next = Vec::new();
+        for value in values {
+            match segment {
+                Segment::Child(selector) => selector.apply(value, root, &mut next),
+                Segment::Descendant(selector) => {
+                    descend(value, &mut |value| selector.apply(value, root, &mut next))
+                }
+            }
+        }
+        values = next;
+    }
+    values
+}
+
+/// Call `f` on a value and all its descendants, parents first
+fn descend<'a>(value: &'a Value, f: &mut impl FnMut(&'a Value)) {
+    f(value);
+    for child in children(value) {
+        descend(child, f);
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(members) => members.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Selector {
+    fn apply<'a>(&self, value: &'a Value, root: &'a Value, out: &mut Vec<&'a Value>) {
+        match self {
+            Selector::Name(name) => out.extend(value.get(name)),
+            Selector::Index(index) => {
+                if let Value::Array(items) = value {
+                    let index = if *index < 0 {
+                        items.len() as i64 + index
+                    } else {
+                        *index
+                    };
+                    out.extend(usize::try_from(index).ok().and_then(|i| items.get(i)));
+                }
+            }
+            Selector::Wildcard => out.extend(children(value)),
+            Selector::Slice { start, end, step } => {
+                if let Value::Array(items) = value {
+                    out.extend(slice(items.len() as i64, *start, *end, *step).map(|i| &items[i]));
+                }
+            }
+            Selector::Union(selectors) => {
+                for selector in selectors {
+                    selector.apply(value, root, out);
+                }
+            }
+            Selector::Filter(filter) => out.extend(
+                children(value)
+                    .into_iter()
+                    .filter(|child| filter.matches(child, root)),
+            ),
+        }
+    }
+}
+
+/// Indices of a slice of an array of `len` elements, in slice order
+fn slice(
+    len: i64,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+) -> impl Iterator<Item = usize> {
+    let step = step.unwrap_or(1);
+    let bound = |i: i64| if i < 0 { len + i } else { i };
+    let (mut i, stop) = if step > 0 {
+        (
+            bound(start.unwrap_or(0)).clamp(0, len),
+            bound(end.unwrap_or(len)).clamp(0, len),
+        )
+    } else {
+        (
+            bound(start.unwrap_or(len - 1)).clamp(-1, len - 1),
+            end.map_or(-1, bound).clamp(-1, len - 1),
+        )
+    };
+    std::iter::from_fn(move || {
+        let inside = (step > 0 && i < stop) || (step < 0 && i > stop);
+        if !inside {
+            return None;
+        }
+        let index = i as usize;
+        i += step;
+        Some(index)
+    })
+}
+
+impl Filter {
+    fn matches(&self, current: &Value, root: &Value) -> bool {
+        match self {
+            Filter::Or(left, right) => left.matches(current, root) || right.matches(current, root),
+            Filter::And(left, right) => left.matches(current, root) && right.matches(current, root),
+            Filter::Not(filter) => !filter.matches(current, root),
+            Filter::Exists(operand) => operand
+                .resolve(current, root)
+                .iter()
+                .any(|value| !matches!(value, Value::Null | Value::Bool(false))),
+            Filter::Compare(left, op, right) => {
+                let left = left.resolve(current, root).into_iter().next();
+                let right = right.resolve(current, root).into_iter().next();
+                let ordering = match (left, right) {
+                    (Some(Value::Number(a)), Some(Value::Number(b))) => {
+                        a.as_f64().partial_cmp(&b.as_f64())
+                    }
+                    (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+                    (a, b) if a == b => Some(Ordering::Equal),
+                    _ => None,
+                };
+                match op {
+                    CompareOp::Eq => ordering == Some(Ordering::Equal),
+                    CompareOp::Ne => ordering != Some(Ordering::Equal),
+                    CompareOp::Lt => ordering == Some(Ordering::Less),
+                    CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    CompareOp::Gt => ordering == Some(Ordering::Greater),
+                    CompareOp::Ge => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, current: &'a Value, root: &'a Value) -> Vec<&'a Value> {
+        match self {
+            Operand::Current(segments) => select(segments, current, root),
+            Operand::Root(segments) => select(segments, root, root),
+            Operand::Literal(value) => vec![value],
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let end = self.pos + s.chars().count();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(s.chars()) {
+            self.pos = end;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_spaces();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", c)))
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let source: String = self.chars.iter().collect();
+        format!(
+            "{} at position {} of JSON path '{}'",
+            message,
+            self.pos + 1,
+            source
+        )
+    }
+
+    /// Segments following `$` or `@`, up to the first character that
+    /// does not start one
+    fn segments(&mut self) -> Result<Vec<Segment>, String> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat_str("..") {
+                let selector = if self.eat('[') {
+                    self.bracket()?
+                } else {
+                    self.dot_selector()?
+                };
+                segments.push(Segment::Descendant(selector));
+            } else if self.eat('.') {
+                segments.push(Segment::Child(self.dot_selector()?));
+            } else if self.eat('[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    /// `*` or a key after a dot
+    fn dot_selector(&mut self) -> Result<Selector, String> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !".[]()=!<>&|,'\"".contains(c))
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("Expected a key"));
+        }
+        Ok(Selector::Name(self.chars[start..self.pos].iter().collect()))
+    }
+
+    /// Selectors between brackets, after the `[`
+    fn bracket(&mut self) -> Result<Selector, String> {
+        self.skip_spaces();
+        if self.eat('?') {
+            let filter = self.or()?;
+            self.expect(']')?;
+            return Ok(Selector::Filter(filter));
+        }
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_spaces();
+            let selector = match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    Selector::Wildcard
+                }
+                Some('\'' | '"') => Selector::Name(self.string()?),
+                _ => self.index_or_slice()?,
+            };
+            selectors.push(selector);
+            self.skip_spaces();
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect(']')?;
+        Ok(if selectors.len() == 1 {
+            selectors.remove(0)
+        } else {
+            Selector::Union(selectors)
+        })
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, String> {
+        let start = self.integer()?;
+        self.skip_spaces();
+        if !self.eat(':') {
+            return start
+                .map(Selector::Index)
+                .ok_or_else(|| self.error("Expected an index, a key or '*'"));
+        }
+        let end = self.integer()?;
+        self.skip_spaces();
+        let step = if self.eat(':') { self.integer()? } else { None };
+        if step == Some(0) {
+            return Err(self.error("Slice step cannot be 0"));
+        }
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>, String> {
+        self.skip_spaces();
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Ok(None);
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits
+            .parse()
+            .map(Some)
+            .map_err(|_| self.error("Invalid index"))
+    }
+
+    /// A quoted string, with `\` escaping the next character
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.chars[self.pos];
+        self.pos += 1;
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' => {
+                    if let Some(escaped) = self.peek() {
+                        self.pos += 1;
+                        text.push(escaped);
+                    }
+                }
+                c if c == quote => return Ok(text),
+                c => text.push(c),
+            }
+        }
+        Err(self.error("Unclosed string"))
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        loop {
+            self.skip_spaces();
+            if !self.eat_str("||") {
+                return Ok(filter);
+            }
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.unary()?;
+        loop {
+            self.skip_spaces();
+            if !self.eat_str("&&") {
+                return Ok(filter);
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        self.skip_spaces();
+        if self.peek() == Some('!') && self.chars.get(self.pos + 1) != Some(&'=') {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let filter = self.or()?;
+            self.expect(')')?;
+            return Ok(filter);
+        }
+        let left = self.operand()?;
+        self.skip_spaces();
+        let op = if self.eat_str("==") {
+            CompareOp::Eq
+        } else if self.eat_str("!=") {
+            CompareOp::Ne
+        } else if self.eat_str("<=") {
+            CompareOp::Le
+        } else if self.eat_str(">=") {
+            CompareOp::Ge
+        } else if self.eat('<') {
+            CompareOp::Lt
+        } else if self.eat('>') {
+            CompareOp::Gt
+        } else {
+            return Ok(Filter::Exists(left));
+        };
+        let right = self.operand()?;
+        Ok(Filter::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        self.skip_spaces();
+        if self.eat('@') {
+            return Ok(Operand::Current(self.segments()?));
+        }
+        if self.eat('$') {
+            return Ok(Operand::Root(self.segments()?));
+        }
+        if matches!(self.peek(), Some('\'' | '"')) {
+            return Ok(Operand::Literal(Value::String(self.string()?)));
+        }
+        for (word, value) in [
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+            ("null", Value::Null),
+        ] {
+            if self.eat_str(word) {
+                return Ok(Operand::Literal(value));
+            }
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || "-+.eE".contains(c))
+        {
+            self.pos += 1;
+        }
+        let number: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str::<serde_json::Number>(&number)
+            .map(|number| Operand::Literal(Value::Number(number)))
+            .map_err(|_| {
+                self.pos = start;
+                self.error("Expected @, $, a string, a number, true, false or null")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path_query() {
+        let document = json!({
+            "data": [
+                {"id": 1, "name": "ada", "active": true, "tags": ["admin"]},
+                {"id": 2, "name": "bob", "active": false},
+                {"id": 3, "name": "eve", "active": true, "score": 7.5}
+            ],
+            "meta": {"total": 3, "next": null, "a.b": "dotted"}
+        });
+        let query = |path: &str| -> Vec<Value> {
+            let path: JsonPath = path.parse().unwrap();
+            path.query(&document).into_iter().cloned().collect()
+        };
+
+        assert_eq!(query("$.data[*].id"), vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(query("meta.total"), vec![json!(3)]);
+        assert_eq!(query("$['meta']['a.b']"), vec![json!("dotted")]);
+        assert_eq!(query("$.data[-1].name"), vec![json!("eve")]);
+        assert_eq!(query("$.data[0,2].id"), vec![json!(1), json!(3)]);
+        assert_eq!(query("$.data[1:].id"), vec![json!(2), json!(3)]);
+        assert_eq!(query("$.data[::-2].id"), vec![json!(3), json!(1)]);
+        assert_eq!(query("$..tags[0]"), vec![json!("admin")]);
+        assert_eq!(query("$.data[?(@.active)].id"), vec![json!(1), json!(3)]);
+        assert_eq!(
+            query("$.data[?(@.id >= 2 && !@.active)].name"),
+            vec![json!("bob")]
+        );
+        assert_eq!(
+            query("$.data[?@.name == 'eve' || @.id < 2].id"),
+            vec![json!(1), json!(3)]
+        );
+        assert_eq!(query("$.data[?(@.score == 7.5)].id"), vec![json!(3)]);
+        assert_eq!(
+            query("$.data[?(@.id == $.meta.total)].name"),
+            vec![json!("eve")]
+        );
+        assert_eq!(query("$.meta.next"), vec![Value::Null]);
+        assert!(query("$.missing[0]").is_empty());
+        assert_eq!(query("$").len(), 1);
+
+        assert!("data".parse::<JsonPath>().unwrap().is_singular());
+        assert!(!"$.data[*]".parse::<JsonPath>().unwrap().is_singular());
+        assert!("$.data[".parse::<JsonPath>().is_err());
+        assert!("$.data[?(@.id ==)]".parse::<JsonPath>().is_err());
+        assert!("$.data[::0]".parse::<JsonPath>().is_err());
+    }
+
+    #[test]
+    fn test_json_path_errors() {
+        let error = |path: &str| path.parse::<JsonPath>().unwrap_err();
+
+        assert_eq!(
+            error("$.data]"),
+            "Unexpected character at position 7 of JSON path '$.data]'"
+        );
+        assert_eq!(
+            error("$.data[0"),
+            "Expected ']' at position 9 of JSON path '$.data[0'"
+        );
+        assert_eq!(
+            error("$..[0]."),
+            "Expected a key at position 8 of JSON path '$..[0].'"
+        );
+        assert_eq!(
+            error("$.data[::0]"),
+            "Slice step cannot be 0 at position 11 of JSON path '$.data[::0]'"
+        );
+        assert_eq!(
+            error("$['data]"),
+            "Unclosed string at position 9 of JSON path '$['data]'"
+        );
+        assert!(error("$.data[?(@.id ==)]").starts_with("Expected @, $, a string"));
+    }
+}
@@ -9,6 +9,7 @@
 //! - [`CollectionManager`]: Manages API collections and their endpoints
 //! - [`HttpClient`]: Executes HTTP requests with a clean, library-friendly API
 //! - [`HttpRequest`] and [`HttpResponse`]: Represent HTTP requests and responses
+//! - [`JsonPath`](json_path::JsonPath): Queries JSON response bodies
 //! - Error types: [`CollectionError`] and [`HttpError`] for handling failures
 //!
 //! ## Basic Usage
@@ -16,7 +17,7 @@
 //! ### Managing Collections
 //!
 //! ```rust,no_run
-//! use coman::core::CollectionManager;
+//! use coman::CollectionManager;
 //! use coman::models::collection::{Collection, Request, Method};
 //!
 //! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
@@ -38,6 +39,7 @@
 //!             method: Method::Get,
 //!             headers: vec![],
 //!             body: None,
+//!             ..Default::default()
 //!         },
 //!         Request {
 //!             name: "create_user".to_string(),
@@ -45,8 +47,10 @@
 //!             method: Method::Post,
 //!             headers: vec![],
 //!             body: Some(r#"{"name": "John Doe"}"#.to_string()),
+//!             ..Default::default()
 //!         },
 //!     ]),
+//!     ..Default::default()
 //! };
 //!
 //! // Add the collection
@@ -67,7 +71,7 @@
 //! ### Making HTTP Requests
 //!
 //! ```rust,no_run
-//! use coman::core::{HttpClient, HttpMethod};
+//! use coman::{HttpClient, HttpMethod};
 //! use std::time::Duration;
 //!
 //! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
@@ -102,7 +106,7 @@
 //! ### Executing Collection Endpoints
 //!
 //! ```rust,no_run
-//! use coman::core::{CollectionManager, HttpClient};
+//! use coman::{CollectionManager, HttpClient};
 //!
 //! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 //! let manager = CollectionManager::default();
@@ -127,7 +131,7 @@
 //! - `HttpResult<T>` for HTTP operations
 //!
 //! ```rust,no_run
-//! use coman::core::{CollectionManager, HttpClient};
+//! use coman::{CollectionManager, HttpClient};
 //!
 //! # async fn example() {
 //! let manager = CollectionManager::default();
@@ -146,11 +150,30 @@
 //! # }
 //! ```
 
+pub mod auth;
 pub mod collection_manager;
 pub mod collection_manager_ops;
+pub mod config;
+pub mod connect;
+pub mod cookies;
+pub mod digest;
 pub mod endpoint_ops;
 pub mod errors;
 pub mod http_client;
 pub mod http_request;
 pub mod http_response;
+pub mod json_path;
+pub mod jwt;
+pub mod oauth;
+pub mod proxy;
+pub mod redirect;
+pub mod retry;
+pub mod signing;
+pub mod sigv4;
+pub mod template;
+#[cfg(test)]
+pub(crate) mod test_server;
+pub mod timing;
+pub mod tls;
+pub mod trace;
 pub mod utils;
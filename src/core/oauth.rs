@@ -0,0 +1,627 @@
+//! OAuth 2.0 - fetches access tokens from a token endpoint and caches them on disk
+//!
+//! Tokens are cached with their expiry in the coman state directory and reused
+//! until they expire. Expired tokens are renewed with their refresh token when
+//! the server issued one, otherwise a new token is requested with the configured grant.
+//!
+//! The authorization code grant needs the user to log in first: see
+//! [`OAuth2::authorization_request`] and [`OAuth2::complete_login`].
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::distr::{Alphanumeric, SampleString};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::core::errors::HttpError;
+use crate::core::http_client::HttpResult;
+use crate::core::utils::{encode_form, hex, unix_time};
+use crate::helper;
+use crate::models::collection::{OAuth2, OAuth2Grant};
+
+/// State file caching access tokens
+pub static OAUTH_TOKENS_FILE: &str = "oauth_tokens.json";
+
+/// Seconds before its expiry at which a cached token is renewed
+const EXPIRY_MARGIN_SECS: u64 = 30;
+
+/// Path of the loopback redirect URI when none is configured
+const REDIRECT_PATH: &str = "/callback";
+
+/// How long `coman auth login` waits for the browser redirect
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long a connection to the loopback listener may take to send its request
+const REDIRECT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause between checks for a connection to the loopback listener
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A cached OAuth 2.0 token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuth2Token {
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Unix time at which the access token expires, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl OAuth2Token {
+    /// Check if the access token has expired or is about to
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| unix_time() + EXPIRY_MARGIN_SECS >= expires_at)
+    }
+}
+
+/// Token endpoint response
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// A pending authorization code login, waiting for the redirect on a loopback listener
+#[derive(Debug)]
+pub struct AuthorizationRequest {
+    /// Authorization URL to open in a browser
+    pub url: String,
+    /// Redirect URI the authorization server sends the code to
+    pub redirect_uri: String,
+    state: String,
+    code_verifier: String,
+    listener: TcpListener,
+}
+
+/// Compute the S256 PKCE code challenge of a code verifier
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Bind the loopback listener for a redirect URI, or for a free port when none is
+/// configured. Returns the listener and the redirect URI.
+fn bind_redirect_listener(redirect_uri: Option<&str>) -> HttpResult<(TcpListener, String)> {
+    let Some(redirect_uri) = redirect_uri else {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        return Ok((
+            listener,
+            format!("http://127.0.0.1:{}{}", port, REDIRECT_PATH),
+        ));
+    };
+
+    let url = Url::parse(redirect_uri).map_err(|e| {
+        HttpError::RequestError(format!("Invalid redirect URI '{}': {}", redirect_uri, e))
+    })?;
+    let host = match url.host_str() {
+        Some("localhost") | Some("127.0.0.1") => "127.0.0.1",
+        Some("[::1]") => "[::1]",
+        _ => {
+            return Err(HttpError::RequestError(format!(
+                "Redirect URI '{}' must be a loopback address (127.0.0.1, [::1] or localhost)",
+                redirect_uri
+            )))
+        }
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    let listener = TcpListener::bind(format!("{}:{}", host, port))?;
+    Ok((listener, redirect_uri.to_string()))
+}
+
+/// Wait on the loopback listener for the redirect to `path` and return its authorization code,
+/// failing when none comes before `timeout`
+fn wait_for_code(
+    listener: &TcpListener,
+    path: &str,
+    state: &str,
+    timeout: Duration,
+) -> HttpResult<String> {
+    let deadline = Instant::now() + timeout;
+    listener.set_nonblocking(true)?;
+    loop {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(HttpError::Other(format!(
+                        "No login redirect received within {} seconds",
+                        timeout.as_secs()
+                    )));
+                }
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // Browsers open connections they may never use: skip silent ones
+        let Ok(request_line) = read_request(&stream, deadline) else {
+            continue;
+        };
+
+        let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+        let url = Url::parse(&format!("http://localhost{}", target))
+            .map_err(|e| HttpError::ResponseError(format!("Invalid redirect: {}", e)))?;
+        if url.path() != path {
+            respond(&mut stream, "404 Not Found", "Not found")?;
+            continue;
+        }
+
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        if let Some(error) = params.get("error") {
+            respond(
+                &mut stream,
+                "400 Bad Request",
+                "Login failed. You can close this window.",
+            )?;
+            return Err(HttpError::ResponseError(format!(
+                "Authorization failed: {} {}",
+                error,
+                params
+                    .get("error_description")
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            )));
+        }
+        if params.get("state").map(String::as_str) != Some(state) {
+            respond(
+                &mut stream,
+                "400 Bad Request",
+                "Login failed. You can close this window.",
+            )?;
+            return Err(HttpError::ResponseError(
+                "Authorization failed: the redirect state does not match".to_string(),
+            ));
+        }
+        let Some(code) = params.get("code") else {
+            respond(&mut stream, "400 Bad Request", "Missing authorization code")?;
+            continue;
+        };
+        respond(
+            &mut stream,
+            "200 OK",
+            "Login complete. You can close this window.",
+        )?;
+        return Ok(code.clone());
+    }
+}
+
+/// Read the request line of a connection and drain its headers, waiting at most
+/// [`REDIRECT_READ_TIMEOUT`] and not past `deadline`
+fn read_request(stream: &TcpStream, deadline: Instant) -> std::io::Result<String> {
+    let timeout = deadline
+        .saturating_duration_since(Instant::now())
+        .clamp(Duration::from_millis(1), REDIRECT_READ_TIMEOUT);
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(timeout))?;
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream);
+    reader.read_line(&mut request_line)?;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    Ok(request_line)
+}
+
+/// Send a plain HTML response to the browser
+fn respond(stream: &mut TcpStream, status: &str, message: &str) -> std::io::Result<()> {
+    let body = format!("<html><body><p>{}</p><p>coman</p></body></html>", message);
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+impl OAuth2 {
+    /// Key of the cached token for these settings. The client secret is hashed,
+    /// so that it is not written to the state file.
+    pub fn cache_key(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            self.grant,
+            self.token_url,
+            self.client_id,
+            hex(&Sha256::digest(self.client_secret.as_bytes())),
+            self.scope.as_deref().unwrap_or_default(),
+            self.username.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Get the cached token for these settings
+    pub fn cached_token(&self) -> Option<OAuth2Token> {
+        let mut tokens: HashMap<String, OAuth2Token> = helper::read_state(OAUTH_TOKENS_FILE);
+        tokens.remove(&self.cache_key())
+    }
+
+    /// Cache a token for these settings
+    pub fn store_token(&self, token: &OAuth2Token) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tokens: HashMap<String, OAuth2Token> = helper::read_state(OAUTH_TOKENS_FILE);
+        tokens.insert(self.cache_key(), token.clone());
+        helper::write_state(OAUTH_TOKENS_FILE, &tokens)
+    }
+
+    /// Remove the cached token for these settings, returning whether there was one
+    pub fn remove_token(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut tokens: HashMap<String, OAuth2Token> = helper::read_state(OAUTH_TOKENS_FILE);
+        if tokens.remove(&self.cache_key()).is_none() {
+            return Ok(false);
+        }
+        helper::write_state(OAUTH_TOKENS_FILE, &tokens)?;
+        Ok(true)
+    }
+
+    /// Get an access token, using the cached one unless it has expired or `refresh` is set
+    pub async fn access_token(&self, client: &Client, refresh: bool) -> HttpResult<String> {
+        let cached = self.cached_token();
+        if let Some(token) = &cached {
+            if !refresh && !token.is_expired() {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let refreshed = match cached.and_then(|t| t.refresh_token) {
+            Some(refresh_token) => self
+                .request_token(client, self.refresh_params(&refresh_token))
+                .await
+                .ok()
+                .map(|mut token| {
+                    token.refresh_token.get_or_insert(refresh_token);
+                    token
+                }),
+            None => None,
+        };
+        let token = match refreshed {
+            Some(token) => token,
+            None => self.request_token(client, self.grant_params()?).await?,
+        };
+
+        // A token that cannot be cached is still valid for this request
+        let _ = self.store_token(&token);
+        Ok(token.access_token)
+    }
+
+    /// Start an authorization code login: bind the loopback redirect listener and
+    /// build the authorization URL with a PKCE code challenge
+    pub fn authorization_request(&self) -> HttpResult<AuthorizationRequest> {
+        let authorize_url = self.authorize_url.as_deref().ok_or_else(|| {
+            HttpError::RequestError("OAuth2 login requires authorize_url".to_string())
+        })?;
+        let mut url = Url::parse(authorize_url).map_err(|e| {
+            HttpError::RequestError(format!("Invalid authorize URL '{}': {}", authorize_url, e))
+        })?;
+
+        let (listener, redirect_uri) = bind_redirect_listener(self.redirect_uri.as_deref())?;
+        let state = Alphanumeric.sample_string(&mut rand::rng(), 24);
+        let code_verifier = Alphanumeric.sample_string(&mut rand::rng(), 64);
+
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &self.client_id)
+                .append_pair("redirect_uri", &redirect_uri);
+            if let Some(scope) = &self.scope {
+                query.append_pair("scope", scope);
+            }
+            query
+                .append_pair("state", &state)
+                .append_pair("code_challenge", &pkce_challenge(&code_verifier))
+                .append_pair("code_challenge_method", "S256");
+        }
+
+        Ok(AuthorizationRequest {
+            url: url.to_string(),
+            redirect_uri,
+            state,
+            code_verifier,
+            listener,
+        })
+    }
+
+    /// Wait for the authorization redirect, exchange its code for tokens and cache them
+    pub async fn complete_login(
+        &self,
+        client: &Client,
+        request: AuthorizationRequest,
+    ) -> HttpResult<OAuth2Token> {
+        let AuthorizationRequest {
+            redirect_uri,
+            state,
+            code_verifier,
+            listener,
+            ..
+        } = request;
+
+        let path = Url::parse(&redirect_uri)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| REDIRECT_PATH.to_string());
+        let code = tokio::task::spawn_blocking(move || {
+            wait_for_code(&listener, &path, &state, LOGIN_TIMEOUT)
+        })
+        .await
+        .map_err(|e| HttpError::Other(e.to_string()))??;
+
+        let params = self.params(vec![
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("code_verifier", &code_verifier),
+        ]);
+        let token = self.request_token(client, params).await?;
+        self.store_token(&token)
+            .map_err(|e| HttpError::Other(format!("Failed to save OAuth2 token: {}", e)))?;
+        Ok(token)
+    }
+
+    /// Form parameters requesting a token with the configured grant
+    fn grant_params(&self) -> HttpResult<Vec<(String, String)>> {
+        Ok(match self.grant {
+            OAuth2Grant::ClientCredentials => {
+                self.params(vec![("grant_type", "client_credentials")])
+            }
+            OAuth2Grant::Password => self.params(vec![
+                ("grant_type", "password"),
+                ("username", self.username.as_deref().unwrap_or_default()),
+                ("password", self.password.as_deref().unwrap_or_default()),
+            ]),
+            OAuth2Grant::RefreshToken => {
+                self.refresh_params(self.refresh_token.as_deref().unwrap_or_default())
+            }
+            OAuth2Grant::AuthorizationCode => {
+                return Err(HttpError::RequestError(format!(
+                    "No valid OAuth2 token for client '{}': log in first with `coman auth login`",
+                    self.client_id
+                )))
+            }
+        })
+    }
+
+    /// Form parameters renewing a token with a refresh token
+    fn refresh_params(&self, refresh_token: &str) -> Vec<(String, String)> {
+        self.params(vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+    }
+
+    /// Add the client credentials and scope to grant parameters
+    fn params(&self, grant: Vec<(&str, &str)>) -> Vec<(String, String)> {
+        let mut params: Vec<(String, String)> = grant
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        params.push(("client_id".to_string(), self.client_id.clone()));
+        if !self.client_secret.is_empty() {
+            params.push(("client_secret".to_string(), self.client_secret.clone()));
+        }
+        if let Some(scope) = &self.scope {
+            params.push(("scope".to_string(), scope.clone()));
+        }
+        params
+    }
+
+    /// Request a token from the token endpoint
+    async fn request_token(
+        &self,
+        client: &Client,
+        params: Vec<(String, String)>,
+    ) -> HttpResult<OAuth2Token> {
+        let response = client
+            .post(&self.token_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(encode_form(&params))
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(HttpError::ResponseError(format!(
+                "OAuth2 token request to {} failed with {}: {}",
+                self.token_url, status, body
+            )));
+        }
+
+        let token: TokenResponse = serde_json::from_str(&body).map_err(|e| {
+            HttpError::ResponseError(format!("Invalid OAuth2 token response: {}", e))
+        })?;
+        Ok(OAuth2Token {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: token.expires_in.map(|secs| unix_time() + secs),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::core::test_server::serve;
+    use crate::helper::tests::temp_data_file;
+    use crate::models::collection::Auth;
+
+    /// Stand-in server issuing `token-N` from `/token` and accepting only the
+    /// second token on any other path
+    fn token_server(requests: Arc<Mutex<Vec<String>>>) -> String {
+        let mut issued = 0;
+        let addr = serve(move |request, stream| {
+            let request = request.text();
+            let (status, body) = if request.starts_with("POST /token") {
+                issued += 1;
+                (
+                    "200 OK",
+                    format!(
+                        r#"{{"access_token":"token-{0}","expires_in":3600,"refresh_token":"refresh-{0}"}}"#,
+                        issued
+                    ),
+                )
+            } else if request
+                .to_lowercase()
+                .contains("authorization: bearer token-2")
+            {
+                ("200 OK", "{}".to_string())
+            } else {
+                ("401 Unauthorized", "{}".to_string())
+            };
+            requests.lock().unwrap().push(request);
+            write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oauth2_refresh_after_401_and_cache() {
+        let _data_file = temp_data_file();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let base = token_server(requests.clone());
+
+        let oauth = OAuth2 {
+            token_url: format!("{}/token", base),
+            client_id: "app".to_string(),
+            client_secret: "secret".to_string(),
+            scope: Some("read".to_string()),
+            ..Default::default()
+        };
+        let request = HttpRequest::new(HttpMethod::Get, &format!("{}/api", base))
+            .auth(Some(Auth::OAuth2(oauth.clone())));
+
+        let response = request.clone().send().await.unwrap();
+        assert_eq!(response.status, 200);
+        {
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 4);
+            assert!(requests[0].contains(
+                "grant_type=client_credentials&client_id=app&client_secret=secret&scope=read"
+            ));
+            assert!(requests[2].contains("grant_type=refresh_token&refresh_token=refresh-1"));
+        }
+
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(requests.lock().unwrap().len(), 5);
+        assert_eq!(
+            oauth.cached_token().unwrap().refresh_token.as_deref(),
+            Some("refresh-2")
+        );
+
+        let other_secret = OAuth2 {
+            client_secret: "other".to_string(),
+            ..oauth.clone()
+        };
+        let other_grant = OAuth2 {
+            grant: OAuth2Grant::AuthorizationCode,
+            ..oauth.clone()
+        };
+        assert!(other_secret.cached_token().is_none());
+        assert!(other_grant.cached_token().is_none());
+        assert!(!oauth.cache_key().contains("secret"));
+
+        assert!(oauth.remove_token().unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oauth2_authorization_code_login() {
+        let _data_file = temp_data_file();
+        // RFC 7636, appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let base = token_server(requests.clone());
+        let oauth = OAuth2 {
+            token_url: format!("{}/token", base),
+            client_id: "app".to_string(),
+            grant: OAuth2Grant::AuthorizationCode,
+            authorize_url: Some("https://id.example.com/authorize".to_string()),
+            ..Default::default()
+        };
+        assert!(oauth.access_token(&Client::new(), false).await.is_err());
+
+        let login = oauth.authorization_request().unwrap();
+        let url = Url::parse(&login.url).unwrap();
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["redirect_uri"], login.redirect_uri);
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        // Simulate the browser following the redirect
+        let redirect = Url::parse(&login.redirect_uri).unwrap();
+        let state = params["state"].clone();
+        let browser = std::thread::spawn(move || {
+            let mut stream =
+                TcpStream::connect(format!("127.0.0.1:{}", redirect.port().unwrap())).unwrap();
+            write!(
+                stream,
+                "GET {}?code=abc&state={} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+                redirect.path(),
+                state
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+
+        let token = oauth.complete_login(&Client::new(), login).await.unwrap();
+        assert_eq!(token.access_token, "token-1");
+        assert!(browser.join().unwrap().starts_with("HTTP/1.1 200 OK"));
+        let token_request = requests.lock().unwrap()[0].clone();
+        assert!(token_request.contains("grant_type=authorization_code&code=abc&redirect_uri="));
+        assert!(token_request.contains("&code_verifier="));
+        assert_eq!(
+            oauth.access_token(&Client::new(), false).await.unwrap(),
+            "token-1"
+        );
+
+        assert!(oauth.remove_token().unwrap());
+    }
+
+    #[test]
+    fn test_wait_for_code_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        // A connection that never sends its request does not block the wait
+        let _silent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let started = Instant::now();
+        let error =
+            wait_for_code(&listener, "/callback", "state", Duration::from_millis(300)).unwrap_err();
+        assert!(error.to_string().contains("No login redirect received"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
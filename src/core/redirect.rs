@@ -0,0 +1,122 @@
+//! Timeouts and redirects - following redirects up to a limit and recording the chain
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::redirect::Policy;
+
+use crate::core::http_response::Redirect;
+use crate::models::collection::RequestSettings;
+
+/// Redirects followed when following is on and no limit is set
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+impl RequestSettings {
+    /// Whether no setting is set
+    pub fn is_empty(&self) -> bool {
+        *self == RequestSettings::default()
+    }
+
+    /// Merge other settings over these ones, set options overriding
+    pub fn merge(&self, other: &RequestSettings) -> RequestSettings {
+        RequestSettings {
+            timeout_ms: other.timeout_ms.or(self.timeout_ms),
+            connect_timeout_ms: other.connect_timeout_ms.or(self.connect_timeout_ms),
+            follow_redirects: other.follow_redirects.or(self.follow_redirects),
+            max_redirects: other.max_redirects.or(self.max_redirects),
+        }
+    }
+}
+
+/// Redirect policy following up to `max` redirects, recording each one in `chain`
+pub(crate) fn redirect_policy(
+    follow: bool,
+    max: Option<usize>,
+    chain: Arc<Mutex<Vec<Redirect>>>,
+) -> Policy {
+    if !follow {
+        return Policy::none();
+    }
+    let max = max.unwrap_or(DEFAULT_MAX_REDIRECTS);
+    Policy::custom(move |attempt| {
+        // The URLs requested so far, the one answering with this redirect last
+        if attempt.previous().len() > max {
+            return attempt.error(format!("too many redirects (max {})", max));
+        }
+        chain.lock().unwrap().push(Redirect {
+            status: attempt.status().as_u16(),
+            url: attempt
+                .previous()
+                .last()
+                .map(|url| url.to_string())
+                .unwrap_or_default(),
+            location: attempt.url().to_string(),
+        });
+        attempt.follow()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::errors::HttpError;
+    use crate::core::http_client::HttpClient;
+    use crate::core::test_server::serve;
+    use crate::models::collection::RequestSettings;
+
+    #[tokio::test]
+    async fn test_redirect_chain() {
+        // Stand-in server redirecting /a to /b to /c
+        let addr = serve(|request, stream| {
+            let response = match request.path() {
+                "/a" => "HTTP/1.1 302 Found\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+                "/b" => {
+                    "HTTP/1.1 301 Moved Permanently\r\nLocation: /c\r\nContent-Length: 0\r\n\r\n"
+                }
+                _ => "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        let base = format!("http://{}", addr);
+        let url = |path: &str| format!("{}{}", base, path);
+
+        let response = HttpClient::new()
+            .with_follow_redirects(true)
+            .get(&url("/a"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.url, url("/c"));
+        let chain: Vec<(u16, &str, &str)> = response
+            .redirects
+            .iter()
+            .map(|r| (r.status, r.url.as_str(), r.location.as_str()))
+            .collect();
+        assert_eq!(
+            chain,
+            [
+                (302, url("/a").as_str(), url("/b").as_str()),
+                (301, url("/b").as_str(), url("/c").as_str())
+            ]
+        );
+
+        // Redirects are returned when not followed
+        let response = HttpClient::new().get(&url("/a")).send().await.unwrap();
+        assert_eq!(response.status, 302);
+        assert!(response.redirects.is_empty());
+
+        // Following fails past the limit
+        let settings = RequestSettings {
+            follow_redirects: Some(true),
+            max_redirects: Some(1),
+            ..Default::default()
+        };
+        let error = HttpClient::new()
+            .with_settings(&settings)
+            .get(&url("/a"))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HttpError::RedirectError(ref message)
+            if message.contains("too many redirects (max 1)")));
+    }
+}
@@ -0,0 +1,181 @@
+//! Retry policies - exponential backoff with jitter, honoring `Retry-After`
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::Method;
+
+use crate::core::errors::HttpError;
+use crate::core::utils::parse_http_date;
+use crate::models::collection::RetryPolicy;
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts with the default statuses and delays
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a response status is retried
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    /// Whether an error is retried: connection errors are, as the request was
+    /// never sent, timeouts only for idempotent methods, which the server may
+    /// have run already, and errors building the request are not
+    pub fn retries_error(&self, error: &HttpError, method: &Method) -> bool {
+        match error {
+            HttpError::ConnectionError(_) => true,
+            HttpError::Timeout => method.is_idempotent(),
+            _ => false,
+        }
+    }
+
+    /// Delay before retry `retry`, 1 for the first one: the `Retry-After` delay
+    /// when the server sent one, or else the doubled delay with equal jitter
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let max = Duration::from_millis(self.max_delay_ms);
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(max);
+        }
+        let delay = self
+            .delay_ms
+            .saturating_mul(1 << retry.saturating_sub(1).min(32))
+            .min(self.max_delay_ms);
+        Duration::from_millis(delay / 2 + rand::random_range(0..=delay - delay / 2))
+    }
+}
+
+/// Delay asked for by a `Retry-After` header, in seconds or as an HTTP date,
+/// `now` being the current Unix time
+pub(crate) fn retry_after(headers: &HeaderMap, now: u64) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(Duration::from_secs(date.saturating_sub(now)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::core::http_client::HttpClient;
+    use crate::core::test_server::serve;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            delay_ms: 100,
+            max_delay_ms: 1_000,
+            ..RetryPolicy::new(5)
+        };
+        for (retry, full) in [(1, 100), (2, 200), (3, 400), (5, 1_000), (40, 1_000)] {
+            let delay = policy.delay(retry, None);
+            assert!(delay >= Duration::from_millis(full / 2), "{:?}", delay);
+            assert!(delay <= Duration::from_millis(full), "{:?}", delay);
+        }
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(300))),
+            Duration::from_millis(300)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(120))),
+            Duration::from_secs(1)
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers, 0), Some(Duration::from_secs(7)));
+        headers.insert(
+            RETRY_AFTER,
+            "Thu, 01 Jan 1970 00:01:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers, 45), Some(Duration::from_secs(15)));
+        assert_eq!(retry_after(&headers, 90), Some(Duration::ZERO));
+
+        let refused = HttpError::ConnectionError("refused".to_string());
+        assert!(policy.retries_error(&refused, &Method::POST));
+        assert!(policy.retries_error(&HttpError::Timeout, &Method::PUT));
+        assert!(!policy.retries_error(&HttpError::Timeout, &Method::POST));
+        assert!(!policy.retries_error(&HttpError::Timeout, &Method::PATCH));
+        let other = HttpError::RequestError("invalid header".to_string());
+        assert!(!policy.retries_error(&other, &Method::GET));
+    }
+
+    #[tokio::test]
+    async fn test_retry_requests() {
+        // Stand-in server failing twice with 503, then answering
+        let requests = Arc::new(AtomicU32::new(0));
+        let received = requests.clone();
+        let addr = serve(move |_, stream| {
+            let response = match received.fetch_add(1, Ordering::SeqCst) {
+                0 => "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n",
+                1 => "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+                _ => "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        let url = format!("http://{}/", addr);
+
+        let policy = RetryPolicy {
+            delay_ms: 20,
+            ..RetryPolicy::new(3)
+        };
+        let response = HttpClient::new()
+            .with_retry(policy.clone())
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        let outcomes: Vec<&str> = response
+            .attempts
+            .iter()
+            .map(|attempt| attempt.outcome.as_str())
+            .collect();
+        assert_eq!(
+            outcomes,
+            ["503 Service Unavailable", "503 Service Unavailable"]
+        );
+        assert_eq!(response.attempts[0].delay, Duration::ZERO);
+        assert!(response.attempts[1].delay >= Duration::from_millis(10));
+
+        // Statuses not retried are returned at once
+        let response = HttpClient::new()
+            .with_retry(RetryPolicy {
+                statuses: vec![429],
+                ..policy.clone()
+            })
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.attempts.is_empty());
+
+        // Connection errors are retried until the attempts run out
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_url = format!("http://{}/", closed.local_addr().unwrap());
+        drop(closed);
+        let error = HttpClient::new()
+            .with_retry(policy)
+            .get(&closed_url)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            HttpError::RetriesExhausted { attempts: 3, ref error }
+                if matches!(**error, HttpError::ConnectionError(_))
+        ));
+    }
+}
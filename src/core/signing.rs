@@ -0,0 +1,191 @@
+//! HMAC request signing - signs a canonical string built from the request
+//!
+//! The canonical string comes from a template with `{timestamp}`, `{method}`,
+//! `{path}`, `{query}`, `{url}` and `{body}` variables, e.g. `{timestamp}.{body}`
+//! for webhook-style APIs.
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Sha384, Sha512};
+use url::Url;
+
+use crate::core::utils::hex;
+use crate::models::collection::{HmacAlgorithm, HmacAuth, SignatureEncoding};
+
+/// Compute an HMAC with the given MAC type
+pub fn compute_hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl HmacAuth {
+    /// Build the string to sign from the template. `\n` in the template stands for a newline.
+    /// The body is inserted as raw bytes, so binary bodies are signed as sent.
+    pub fn canonical_string(
+        &self,
+        method: &str,
+        url: &Url,
+        body: &[u8],
+        timestamp: u64,
+    ) -> Vec<u8> {
+        let template = self.template.replace("\\n", "\n");
+        let mut out = Vec::with_capacity(template.len() + body.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            out.extend_from_slice(&rest.as_bytes()[..start]);
+            let tail = &rest[start..];
+            let value = tail.find('}').and_then(|end| {
+                let value = match &tail[1..end] {
+                    "timestamp" => timestamp.to_string().into_bytes(),
+                    "method" => method.as_bytes().to_vec(),
+                    "path" => url.path().as_bytes().to_vec(),
+                    "query" => url.query().unwrap_or_default().as_bytes().to_vec(),
+                    "url" => url.as_str().as_bytes().to_vec(),
+                    "body" => body.to_vec(),
+                    _ => return None,
+                };
+                Some((value, end))
+            });
+            match value {
+                Some((value, end)) => {
+                    out.extend_from_slice(&value);
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    out.push(b'{');
+                    rest = &tail[1..];
+                }
+            }
+        }
+        out.extend_from_slice(rest.as_bytes());
+        out
+    }
+
+    /// Sign a request, returning the headers to add: the signature and, when
+    /// configured, the timestamp
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &Url,
+        body: &[u8],
+        timestamp: u64,
+    ) -> Vec<(String, String)> {
+        let message = self.canonical_string(method, url, body, timestamp);
+        let key = self.secret.as_bytes();
+        let mac = match self.algorithm {
+            HmacAlgorithm::Sha256 => compute_hmac::<Hmac<Sha256>>(key, &message),
+            HmacAlgorithm::Sha384 => compute_hmac::<Hmac<Sha384>>(key, &message),
+            HmacAlgorithm::Sha512 => compute_hmac::<Hmac<Sha512>>(key, &message),
+        };
+        let signature = match self.encoding {
+            SignatureEncoding::Hex => hex(&mac),
+            SignatureEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(mac),
+        };
+
+        let mut headers = Vec::new();
+        if let Some(timestamp_header) = &self.timestamp_header {
+            headers.push((timestamp_header.clone(), timestamp.to_string()));
+        }
+        headers.push((self.header.clone(), format!("{}{}", self.prefix, signature)));
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::models::collection::Auth;
+
+    #[test]
+    fn test_hmac_sign() {
+        let url = Url::parse("https://partner.example.com/hooks/order?id=7&x=a%20b").unwrap();
+        let body = br#"{"a":1}"#;
+
+        let hmac: HmacAuth = "secret=whsec;prefix=sha256=".parse().unwrap();
+        assert_eq!(
+            hmac.sign("POST", &url, body, 1_700_000_000),
+            vec![
+                ("X-Timestamp".to_string(), "1700000000".to_string()),
+                (
+                    "X-Signature".to_string(),
+                    "sha256=8ad37ba156048ae0e0a5533c75cdf26fee88b07f93cb57ee4c80adb053012032"
+                        .to_string()
+                ),
+            ]
+        );
+
+        let hmac: HmacAuth = r"secret=whsec;algorithm=sha512;encoding=base64;header=X-Sig;timestamp_header=;template={method}\n{path}\n{query}\n{timestamp}\n{body}"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            hmac.sign("POST", &url, body, 1_700_000_000),
+            vec![(
+                "X-Sig".to_string(),
+                "qQNUoeE4NoIUDGujHVV7BbYph0/4KTv+45QEdFDYZFhBh58iGwsT+ItFybL/ttT1J44Ga1HMaO4u1/S/CjS6Yg=="
+                    .to_string()
+            )]
+        );
+
+        // Binary bodies are signed as sent, not as lossy UTF-8
+        let body = [0x7b, 0xff, 0xfe, 0x00, 0x7d];
+        let hmac: HmacAuth = "secret=whsec;timestamp_header=;template={timestamp}.{body}"
+            .parse()
+            .unwrap();
+        let message = hmac.canonical_string("POST", &url, &body, 1_700_000_000);
+        assert_eq!(message, [b"1700000000.".as_slice(), &body].concat());
+        assert_eq!(
+            hmac.sign("POST", &url, &body, 1_700_000_000)[0].1,
+            hex(&compute_hmac::<Hmac<Sha256>>(b"whsec", &message))
+        );
+
+        // Unknown variables are kept as written
+        let hmac: HmacAuth = "secret=whsec;template={nonce}.{body".parse().unwrap();
+        assert_eq!(
+            hmac.canonical_string("POST", &url, b"x", 0),
+            b"{nonce}.{body"
+        );
+    }
+
+    #[test]
+    fn test_hmac_errors() {
+        assert_eq!(
+            "secret=whsec;algorithm=md5"
+                .parse::<HmacAuth>()
+                .unwrap_err(),
+            "Invalid HMAC algorithm 'md5'. Use sha256, sha384 or sha512"
+        );
+        assert_eq!(
+            "secret=whsec;encoding=base32"
+                .parse::<HmacAuth>()
+                .unwrap_err(),
+            "Invalid HMAC encoding 'base32'. Use hex or base64"
+        );
+        assert_eq!(
+            "secret=whsec;digest=sha256"
+                .parse::<HmacAuth>()
+                .unwrap_err(),
+            "Unknown HMAC option 'digest'"
+        );
+        assert_eq!(
+            "header=X-Sig".parse::<HmacAuth>().unwrap_err(),
+            "HMAC requires secret"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hmac_refuses_multipart_forms() {
+        let error = HttpRequest::new(HttpMethod::Post, "http://127.0.0.1:9/upload")
+            .multipart(vec!["name=value".parse().unwrap()])
+            .auth(Some(Auth::Hmac("secret=whsec".parse().unwrap())))
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Request error: HMAC signing does not support multipart forms"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! AWS Signature Version 4 - signs requests with AWS credentials
+//!
+//! See <https://docs.aws.amazon.com/IAM/latest/UserGuide/create-signed-request.html>.
+//! The signature covers the method, URL, headers and a hash of the body, so it is
+//! computed from the final request right before it is sent.
+
+use hmac::{Hmac, Mac};
+use percent_encoding::{percent_decode_str, utf8_percent_encode};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::core::utils::{hex, utc_date_time, URI_COMPONENT};
+use crate::models::collection::AwsSigV4;
+
+/// Payload hash used when the body cannot be hashed up front (e.g. multipart)
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Hex-encoded SHA-256 hash of a request payload
+pub fn payload_hash(payload: &[u8]) -> String {
+    hex(&Sha256::digest(payload))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, URI_COMPONENT).to_string()
+}
+
+impl AwsSigV4 {
+    /// Sign a request made at `time` (Unix seconds), returning the headers to add to it:
+    /// `Authorization`, `X-Amz-Date` and, when needed, `X-Amz-Security-Token` and
+    /// `X-Amz-Content-Sha256`.
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &[(String, String)],
+        payload_hash: &str,
+        time: u64,
+    ) -> Vec<(String, String)> {
+        let (year, month, day, hour, minute, second) = utc_date_time(time);
+        let date = format!("{:04}{:02}{:02}", year, month, day);
+        let amz_date = format!("{}T{:02}{:02}{:02}Z", date, hour, minute, second);
+
+        let mut added = vec![("X-Amz-Date".to_string(), amz_date.clone())];
+        if let Some(token) = &self.session_token {
+            added.push(("X-Amz-Security-Token".to_string(), token.clone()));
+        }
+        if self.service == "s3" {
+            added.push(("X-Amz-Content-Sha256".to_string(), payload_hash.to_string()));
+        }
+
+        // Canonical headers: lowercase names, sorted, with trimmed values
+        let mut canonical: Vec<(String, String)> = headers
+            .iter()
+            .chain(added.iter())
+            .map(|(name, value)| {
+                (
+                    name.to_lowercase(),
+                    value.split_whitespace().collect::<Vec<_>>().join(" "),
+                )
+            })
+            .collect();
+        if !canonical.iter().any(|(name, _)| name == "host") {
+            let host = match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                _ => String::new(),
+            };
+            canonical.push(("host".to_string(), host));
+        }
+        canonical.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut canonical_headers = String::new();
+        let mut signed_headers: Vec<&str> = Vec::new();
+        for (name, value) in &canonical {
+            if signed_headers.last() == Some(&name.as_str()) {
+                // Repeated headers are combined into one comma-separated value
+                canonical_headers.pop();
+                canonical_headers.push_str(&format!(",{}\n", value));
+            } else {
+                canonical_headers.push_str(&format!("{}:{}\n", name, value));
+                signed_headers.push(name);
+            }
+        }
+        let signed_headers = signed_headers.join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            self.canonical_uri(url),
+            canonical_query(url),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = [date.as_str(), &self.region, &self.service, "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_key).into_bytes(),
+                |key, part| hmac_sha256(&key, part),
+            );
+        let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+        added.push((
+            "Authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key, scope, signed_headers, signature
+            ),
+        ));
+        added
+    }
+
+    /// URI-encoded path; encoded twice for every service but S3
+    fn canonical_uri(&self, url: &Url) -> String {
+        let path = url
+            .path()
+            .split('/')
+            .map(|segment| {
+                let encoded = encode(&percent_decode_str(segment).decode_utf8_lossy());
+                if self.service == "s3" {
+                    encoded
+                } else {
+                    encode(&encoded)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.is_empty() {
+            "/".to_string()
+        } else {
+            path
+        }
+    }
+}
+
+/// Query parameters URI-encoded and sorted by name, then value. A `+` is a
+/// literal plus, as for AWS, not a space.
+fn canonical_query(url: &Url) -> String {
+    let decode = |s: &str| encode(&percent_decode_str(s).decode_utf8_lossy());
+    let mut pairs: Vec<(String, String)> = url
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::utils::build_url;
+
+    /// 2015-08-30T12:36:00Z, the time used by the AWS SigV4 test suite
+    const TEST_TIME: u64 = 1_440_938_160;
+
+    fn credentials(service: &str) -> AwsSigV4 {
+        AwsSigV4 {
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+            region: "us-east-1".to_string(),
+            service: service.to_string(),
+        }
+    }
+
+    fn signature(headers: &[(String, String)]) -> &str {
+        let (_, authorization) = headers.iter().find(|(k, _)| k == "Authorization").unwrap();
+        authorization.rsplit("Signature=").next().unwrap()
+    }
+
+    #[test]
+    fn test_sigv4_test_suite() {
+        let aws = credentials("service");
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+
+        // get-vanilla
+        let headers = aws.sign("GET", &url, &[], &payload_hash(b""), TEST_TIME);
+        assert_eq!(
+            headers[1].1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        assert_eq!(headers[0].1, "20150830T123600Z");
+
+        // post-vanilla
+        let headers = aws.sign("POST", &url, &[], &payload_hash(b""), TEST_TIME);
+        assert_eq!(
+            signature(&headers),
+            "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        );
+
+        // get-vanilla-query-order-key-case, get-vanilla-query-order-key,
+        // get-vanilla-query-order-value and get-vanilla-query-unreserved
+        let unreserved = "-._~0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        for (query, expected) in [
+            (
+                "Param2=value2&Param1=value1".to_string(),
+                "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500",
+            ),
+            (
+                "Param1=value2&Param1=Value1".to_string(),
+                "eedbc4e291e521cf13422ffca22be7d2eb8146eecf653089df300a15b2382bd1",
+            ),
+            (
+                "Param1=value2&Param1=value1".to_string(),
+                "5772eed61e12b33fae39ee5e7012498b51d56abc0abb7c60486157bd471c4694",
+            ),
+            (
+                format!("{0}={0}", unreserved),
+                "9c3e54bfcdf0b19771a7f523ee5669cdf59bc7cc0884027167c21bb143a40197",
+            ),
+        ] {
+            let url = Url::parse(&format!("https://example.amazonaws.com/?{}", query)).unwrap();
+            let headers = aws.sign("GET", &url, &[], &payload_hash(b""), TEST_TIME);
+            assert_eq!(signature(&headers), expected, "{}", query);
+        }
+
+        // get-space: the suite signs the path as written, like S3, while other
+        // services sign the path as sent encoded once more
+        let url = Url::parse("https://example.amazonaws.com/example space/").unwrap();
+        assert_eq!(credentials("s3").canonical_uri(&url), "/example%20space/");
+        assert_eq!(aws.canonical_uri(&url), "/example%2520space/");
+
+        // post-x-www-form-urlencoded
+        let content_type = vec![(
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        )];
+        let headers = aws.sign(
+            "POST",
+            &Url::parse("https://example.amazonaws.com/").unwrap(),
+            &content_type,
+            &payload_hash(b"Param1=value1"),
+            TEST_TIME,
+        );
+        assert_eq!(
+            signature(&headers),
+            "ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        );
+
+        // get-header-value-trim
+        let trimmed = vec![
+            ("My-Header1".to_string(), " value1".to_string()),
+            ("My-Header2".to_string(), r#" "a   b   c""#.to_string()),
+        ];
+        let headers = aws.sign(
+            "GET",
+            &Url::parse("https://example.amazonaws.com/").unwrap(),
+            &trimmed,
+            &payload_hash(b""),
+            TEST_TIME,
+        );
+        assert!(headers[1]
+            .1
+            .contains("SignedHeaders=host;my-header1;my-header2;x-amz-date"));
+        assert_eq!(
+            signature(&headers),
+            "acc3ed3afb60bb290fc8d2dd0098b9911fcaa05412b367055dee359757a9c736"
+        );
+
+        // A space in a query parameter is sent and signed as `%20`, a `+` as a plus
+        let url = build_url(
+            "https://example.amazonaws.com/",
+            &[("q".to_string(), "a b".to_string())],
+        )
+        .unwrap();
+        assert_eq!(url, "https://example.amazonaws.com/?q=a%20b");
+        let headers = aws.sign(
+            "GET",
+            &Url::parse(&url).unwrap(),
+            &[],
+            &payload_hash(b""),
+            TEST_TIME,
+        );
+        assert_eq!(
+            signature(&headers),
+            "87ff43fc767921116dce7f8d6f2b33a53470d1f0784a931ee9319fdc2d81e9e2"
+        );
+        let url = Url::parse("https://example.amazonaws.com/?q=a+b").unwrap();
+        assert_eq!(canonical_query(&url), "q=a%2Bb");
+
+        // IAM ListUsers example from the AWS documentation
+        let url =
+            Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+        let content_type = vec![(
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+        )];
+        let headers =
+            credentials("iam").sign("GET", &url, &content_type, &payload_hash(b""), TEST_TIME);
+        assert_eq!(
+            signature(&headers),
+            "5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Template expressions - `{{...}}` placeholders in URLs, headers and bodies
+//!
+//! Interactive placeholders have the form
+//! `{{? name | default=x | secret | choices=a,b}}` and are resolved by the
+//! caller (e.g. by prompting the user) before the request is sent.
+//!
+//! Generators have the form `{{$name args}}` and produce a value each time
+//! they are rendered:
+//!
+//! - `{{$uuid}}`: random UUID v4
+//! - `{{$timestamp}}`: Unix timestamp in seconds
+//! - `{{$isoDate}}`: current UTC date and time in ISO 8601 format
+//! - `{{$randomInt min max}}`: random integer between `min` and `max` (inclusive)
+//! - `{{$randomEmail}}`: random email address
+//! - `{{$base64 text}}`: `text` encoded as base64
+//! - `{{$env NAME}}`: value of the environment variable `NAME`
+//! - `{{$file path}}`: contents of the file at `path`
+
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use rand::distr::{Alphanumeric, SampleString};
+
+use crate::core::utils::utc_date_time;
+
+/// An interactive placeholder: `{{? name | default=x | secret | choices=a,b}}`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Placeholder {
+    /// Name of the value, used as the prompt label
+    pub name: String,
+    /// Value used when nothing is entered
+    pub default: Option<String>,
+    /// Whether the value must not be echoed or remembered
+    pub secret: bool,
+    /// Allowed values, empty if any value is accepted
+    pub choices: Vec<String>,
+}
+
+impl FromStr for Placeholder {
+    type Err = String;
+
+    /// Parse the inside of a `{{? ...}}` expression, without the braces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expr = s
+            .trim()
+            .strip_prefix('?')
+            .ok_or_else(|| format!("Not a placeholder: '{}'", s))?;
+
+        let mut parts = expr.split('|').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        if name.is_empty() {
+            return Err(format!("Placeholder without a name: '{{{{{}}}}}'", s));
+        }
+
+        let mut placeholder = Placeholder {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for option in parts {
+            match option.split_once('=') {
+                Some(("default", value)) => placeholder.default = Some(value.trim().to_string()),
+                Some(("choices", values)) => {
+                    placeholder.choices = values
+                        .split(',')
+                        .map(|c| c.trim().to_string())
+                        .filter(|c| !c.is_empty())
+                        .collect()
+                }
+                None if option == "secret" => placeholder.secret = true,
+                _ => {
+                    return Err(format!(
+                        "Invalid option '{}' in placeholder '{}'",
+                        option, name
+                    ))
+                }
+            }
+        }
+        Ok(placeholder)
+    }
+}
+
+/// Replace every `{{...}}` expression in `text` with the value returned by `resolve`,
+/// leaving the expression in place when `resolve` returns `None`.
+pub fn replace_expressions<F>(text: &str, mut resolve: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let Some(len) = tail[2..].find("}}") else {
+            out.push_str(tail);
+            return out;
+        };
+        let expr = &tail[2..2 + len];
+        match resolve(expr) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&tail[..len + 4]),
+        }
+        rest = &tail[len + 4..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Find the distinct placeholders in `text`, in order of appearance
+pub fn find_placeholders(text: &str) -> Result<Vec<Placeholder>, String> {
+    let mut found: Vec<Placeholder> = Vec::new();
+    let mut error = None;
+    replace_expressions(text, |expr| {
+        if expr.trim_start().starts_with('?') {
+            match expr.parse::<Placeholder>() {
+                Ok(placeholder) => merge_placeholder(&mut found, placeholder),
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        None
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(found),
+    }
+}
+
+/// Add a placeholder to the list, merging it with an earlier one of the same name
+pub fn merge_placeholder(found: &mut Vec<Placeholder>, placeholder: Placeholder) {
+    match found.iter_mut().find(|p| p.name == placeholder.name) {
+        Some(existing) => {
+            existing.secret |= placeholder.secret;
+            if existing.default.is_none() {
+                existing.default = placeholder.default;
+            }
+            if existing.choices.is_empty() {
+                existing.choices = placeholder.choices;
+            }
+        }
+        None => found.push(placeholder),
+    }
+}
+
+/// Replace the placeholders in `text` with their values, leaving unknown ones in place
+pub fn fill_placeholders(text: &str, values: &[(String, String)]) -> String {
+    replace_expressions(text, |expr| {
+        let placeholder = expr.parse::<Placeholder>().ok()?;
+        values
+            .iter()
+            .find(|(name, _)| *name == placeholder.name)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+/// Evaluate a generator expression (the inside of `{{$...}}`, without the braces)
+pub fn generate(expr: &str) -> Result<String, String> {
+    let expr = expr
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| format!("Not a generator: '{}'", expr))?;
+    let (name, args) = expr.split_once(char::is_whitespace).unwrap_or((expr, ""));
+    let args = args.trim();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+
+    match name {
+        "uuid" => Ok(uuid::Uuid::new_v4().to_string()),
+        "timestamp" => Ok(now.as_secs().to_string()),
+        "isoDate" => {
+            let (year, month, day, hour, minute, second) = utc_date_time(now.as_secs());
+            Ok(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                now.subsec_millis()
+            ))
+        }
+        "randomInt" => {
+            let bounds = args
+                .split_whitespace()
+                .map(|a| a.parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Invalid $randomInt bounds '{}': {}", args, e))?;
+            let (min, max) = match bounds[..] {
+                [] => (0, 1000),
+                [min, max] if min <= max => (min, max),
+                _ => return Err(format!("Invalid $randomInt bounds '{}'. Use MIN MAX", args)),
+            };
+            Ok(rand::random_range(min..=max).to_string())
+        }
+        "randomEmail" => {
+            let user = Alphanumeric
+                .sample_string(&mut rand::rng(), 10)
+                .to_lowercase();
+            Ok(format!("{}@example.com", user))
+        }
+        "base64" => Ok(base64::engine::general_purpose::STANDARD.encode(args)),
+        "env" => {
+            std::env::var(args).map_err(|_| format!("Environment variable '{}' is not set", args))
+        }
+        "file" => std::fs::read_to_string(args)
+            .map_err(|e| format!("Failed to read file '{}': {}", args, e)),
+        _ => Err(format!("Unknown template function: ${}", name)),
+    }
+}
+
+/// Render the generator expressions in `text`, leaving other expressions in place
+pub fn render_generators(text: &str) -> Result<String, String> {
+    let mut error = None;
+    let rendered = replace_expressions(text, |expr| {
+        if !expr.trim_start().starts_with('$') || error.is_some() {
+            return None;
+        }
+        match generate(expr) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(rendered),
+    }
+}
+
+/// Render the generator expressions in the values of key-value pairs
+pub fn render_pairs(pairs: &[(String, String)]) -> Result<Vec<(String, String)>, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), render_generators(value)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_placeholder() {
+        let placeholder: Placeholder = "? env | default=dev | choices=dev, prod | secret"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            placeholder,
+            Placeholder {
+                name: "env".to_string(),
+                default: Some("dev".to_string()),
+                secret: true,
+                choices: vec!["dev".to_string(), "prod".to_string()],
+            }
+        );
+        assert!("? ".parse::<Placeholder>().is_err());
+        assert!("? token | hidden".parse::<Placeholder>().is_err());
+        assert!("$uuid".parse::<Placeholder>().is_err());
+    }
+
+    #[test]
+    fn test_find_and_fill_placeholders() {
+        let body = r#"{"user": "{{? user}}", "pass": "{{? pass | secret}}", "again": "{{?user|default=bob}}", "id": "{{$uuid}}"}"#;
+
+        let found = find_placeholders(body).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "user");
+        assert_eq!(found[0].default.as_deref(), Some("bob"));
+        assert!(found[1].secret);
+
+        let values = vec![
+            ("user".to_string(), "alice".to_string()),
+            ("pass".to_string(), "s3cret".to_string()),
+        ];
+        assert_eq!(
+            fill_placeholders(body, &values),
+            r#"{"user": "alice", "pass": "s3cret", "again": "alice", "id": "{{$uuid}}"}"#
+        );
+        assert_eq!(fill_placeholders("a {{? user", &values), "a {{? user");
+    }
+
+    #[test]
+    fn test_render_generators() {
+        std::env::set_var("COMAN_TEMPLATE_TEST", "secret");
+        let text = "{{$uuid}}|{{ $timestamp }}|{{$isoDate}}|{{$randomInt 5 5}}|{{$randomEmail}}|{{$base64 user:pass}}|{{$env COMAN_TEMPLATE_TEST}}|{{? keep}}";
+
+        let rendered = render_generators(text).unwrap();
+        let parts: Vec<&str> = rendered.split('|').collect();
+
+        assert!(uuid::Uuid::parse_str(parts[0]).is_ok());
+        assert!(parts[1].parse::<u64>().unwrap() > 1_700_000_000);
+        assert_eq!(parts[2].len(), "2026-01-01T00:00:00.000Z".len());
+        assert!(parts[2].ends_with('Z'));
+        assert_eq!(parts[3], "5");
+        assert!(parts[4].ends_with("@example.com"));
+        assert_eq!(parts[5], "dXNlcjpwYXNz");
+        assert_eq!(parts[6], "secret");
+        assert_eq!(parts[7], "{{? keep}}");
+
+        assert!(render_generators("{{$nope}}").is_err());
+        assert!(render_generators("{{$randomInt 9 1}}").is_err());
+        assert!(render_generators("{{$env COMAN_TEMPLATE_UNSET}}").is_err());
+    }
+}
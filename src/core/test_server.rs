@@ -0,0 +1,98 @@
+//! Stand-in HTTP servers for tests, answering each request on a connection of its own
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+
+/// A request received by a stand-in server
+pub struct Request {
+    /// Request line and header lines, as received
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The request line, such as `GET /path HTTP/1.1`
+    pub fn line(&self) -> &str {
+        self.head.lines().next().unwrap_or_default()
+    }
+
+    /// The request target of the request line
+    pub fn path(&self) -> &str {
+        self.line().split(' ').nth(1).unwrap_or_default()
+    }
+
+    /// The value of a header, matching its name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// The whole request as text
+    pub fn text(&self) -> String {
+        format!("{}{}", self.head, String::from_utf8_lossy(&self.body))
+    }
+}
+
+/// Read a request: its head up to the empty line, then a `Content-Length` body
+fn read_request(stream: impl Read) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        if line == "\r\n" {
+            break;
+        }
+        head.push_str(&line);
+    }
+    let mut request = Request {
+        head,
+        body: Vec::new(),
+    };
+    let length = request
+        .header("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).ok()?;
+    Some(request)
+}
+
+/// Start a stand-in server on a local port, calling `respond` with each request
+/// and a stream to write the raw response to. Returns the server address.
+pub fn serve<F>(mut respond: F) -> SocketAddr
+where
+    F: FnMut(Request, &mut dyn Write) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if let Some(request) = read_request(&stream) {
+                respond(request, &mut stream);
+            }
+        }
+    });
+    addr
+}
+
+/// Start a stand-in server on a Unix socket, like [`serve`]
+#[cfg(unix)]
+pub fn serve_unix<F>(path: &std::path::Path, mut respond: F)
+where
+    F: FnMut(Request, &mut dyn Write) + Send + 'static,
+{
+    let _ = std::fs::remove_file(path);
+    let listener = std::os::unix::net::UnixListener::bind(path).unwrap();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if let Some(request) = read_request(&stream) {
+                respond(request, &mut stream);
+            }
+        }
+    });
+}
@@ -0,0 +1,212 @@
+//! Request timings - DNS lookup, connect, time to first byte and download phases
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::core::http_response::Timings;
+
+/// Clock of a request, recording when the lookups and connections made by its
+/// client start and end
+#[derive(Debug)]
+pub(crate) struct RequestClock {
+    start: Mutex<Instant>,
+    dns: Mutex<Option<(Instant, Instant)>>,
+    connect: Mutex<Option<(Instant, Instant)>>,
+}
+
+impl RequestClock {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            start: Mutex::new(Instant::now()),
+            dns: Mutex::new(None),
+            connect: Mutex::new(None),
+        })
+    }
+
+    /// Start timing the request, once its client is built
+    pub(crate) fn start(&self) {
+        *self.start.lock().unwrap() = Instant::now();
+    }
+
+    /// Record a host name lookup that started at `start` and ends now. Only the
+    /// first one is kept, later ones being for redirects or retries.
+    pub(crate) fn record_dns(&self, start: Instant) {
+        record(&self.dns, start);
+    }
+
+    /// Record a connection that started at `start` and is established now
+    pub(crate) fn record_connect(&self, start: Instant) {
+        record(&self.connect, start);
+    }
+
+    /// The phases of a request whose response headers came at `headers` and
+    /// whose body was read by `done`
+    pub(crate) fn timings(&self, headers: Instant, done: Instant) -> Timings {
+        let start = *self.start.lock().unwrap();
+        let dns = *self.dns.lock().unwrap();
+        let connect = *self.connect.lock().unwrap();
+        // The connector resolves the host first, so its lookup is not counted twice
+        let connected = connect.map(|(start, end)| match dns {
+            Some((_, dns_end)) if dns_end > start => (dns_end, end),
+            _ => (start, end),
+        });
+        let waiting_from = connected.map_or(start, |(_, end)| end);
+
+        Timings {
+            dns: dns.map(|(start, end)| end - start),
+            connect: connected.map(|(start, end)| end.saturating_duration_since(start)),
+            ttfb: headers.saturating_duration_since(waiting_from),
+            download: done.saturating_duration_since(headers),
+            total: done - start,
+        }
+    }
+}
+
+fn record(phase: &Mutex<Option<(Instant, Instant)>>, start: Instant) {
+    let mut phase = phase.lock().unwrap();
+    if phase.is_none() {
+        *phase = Some((start, Instant::now()));
+    }
+}
+
+/// Connector layer timing the connections of a client
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectTiming(pub(crate) Arc<RequestClock>);
+
+impl<S> Layer<S> for ConnectTiming {
+    type Service = ConnectTimingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectTimingService {
+            inner,
+            clock: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectTimingService<S> {
+    inner: S,
+    clock: Arc<RequestClock>,
+}
+
+impl<S, R> Service<R> for ConnectTimingService<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let start = Instant::now();
+        let clock = self.clock.clone();
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            let conn = connecting.await?;
+            clock.record_connect(start);
+            Ok(conn)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::core::test_server::serve;
+
+    #[tokio::test]
+    async fn test_request_timings() {
+        // Stand-in server answering after 50 ms, then sending the body in two parts
+        let port = serve(|_, stream| {
+            std::thread::sleep(Duration::from_millis(50));
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nab").unwrap();
+            stream.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            write!(stream, "cd").unwrap();
+        })
+        .port();
+
+        let response = HttpRequest::new(HttpMethod::Get, &format!("http://localhost:{}/", port))
+            .send()
+            .await
+            .unwrap();
+        let timings = response.timings;
+        assert_eq!(response.body, "abcd");
+        assert!(timings.dns.is_some());
+        assert!(timings.connect.is_some());
+        assert!(timings.ttfb >= Duration::from_millis(50));
+        assert!(timings.download >= Duration::from_millis(40));
+        assert!(
+            timings.total
+                >= timings.dns.unwrap()
+                    + timings.connect.unwrap()
+                    + timings.ttfb
+                    + timings.download
+        );
+        assert_eq!(response.elapsed_ms, timings.total.as_millis());
+
+        // IP addresses are not looked up
+        let response = HttpRequest::new(HttpMethod::Get, &format!("http://127.0.0.1:{}/", port))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.timings.dns, None);
+    }
+
+    /// Connector refusing every connection
+    struct Refused;
+
+    impl Service<()> for Refused {
+        type Response = ();
+        type Error = std::io::Error;
+        type Future = std::future::Ready<Result<(), std::io::Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            std::future::ready(Err(std::io::ErrorKind::ConnectionRefused.into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_connection_timings() {
+        let clock = RequestClock::new();
+        let mut connector = ConnectTiming(clock.clone()).layer(Refused);
+        assert!(connector.call(()).await.is_err());
+
+        // Without a connection, the wait is counted from the start
+        let headers = Instant::now();
+        let timings = clock.timings(headers, headers + Duration::from_millis(5));
+        assert_eq!((timings.dns, timings.connect), (None, None));
+        assert_eq!(timings.download, Duration::from_millis(5));
+        assert_eq!(timings.total, timings.ttfb + timings.download);
+
+        // Only the first connection is kept, and phases ending out of order
+        // don't underflow
+        let start = Instant::now();
+        clock.record_connect(start);
+        clock.record_connect(Instant::now());
+        let timings = clock.timings(start, start);
+        assert!(timings.connect.is_some());
+        assert_eq!(timings.ttfb, Duration::ZERO);
+        assert_eq!(timings.download, Duration::ZERO);
+    }
+}
@@ -0,0 +1,431 @@
+//! Wire traces - the raw bytes of traced requests, like `curl --trace-ascii`
+//!
+//! reqwest logs what each connection reads and writes, after TLS, when built
+//! with `connection_verbose`. While a traced request runs, those log records
+//! are turned back into bytes and written to its trace, HTTP/1 heads as text
+//! and bodies as text or a hex dump.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::core::errors::HttpError;
+use crate::core::http_client::HttpResult;
+
+/// Log target of reqwest's connection logs
+const WIRE_TARGET: &str = "reqwest::connect::verbose";
+
+/// Longest HTTP/1 head buffered before the stream is dumped as raw data
+const MAX_HEAD: usize = 64 * 1024;
+
+/// The trace written by the traced request running, if any
+static ACTIVE: Mutex<Option<Arc<Trace>>> = Mutex::new(None);
+
+/// Whether the wire logger could be installed as the process logger
+static LOGGER: OnceLock<bool> = OnceLock::new();
+
+/// Destination of a wire trace
+pub struct Trace {
+    writer: Mutex<Box<dyn Write + Send>>,
+    streams: Mutex<HashMap<(String, Direction), Stream>>,
+}
+
+impl std::fmt::Debug for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trace").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Direction {
+    Send,
+    Recv,
+}
+
+/// Where a connection stream is at
+#[derive(Debug)]
+enum Stream {
+    /// Reading an HTTP/1 head
+    Head(Vec<u8>),
+    /// Reading a body of a known length, or up to the end of the connection
+    Body(Option<usize>),
+    /// Not HTTP/1, e.g. HTTP/2 frames
+    Raw,
+}
+
+impl Trace {
+    /// Trace to any writer
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+            streams: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Trace to a file, or to standard output for `-`
+    pub fn open(path: &str) -> io::Result<Self> {
+        if path == "-" {
+            return Ok(Self::new(io::stdout()));
+        }
+        Ok(Self::new(File::create(path)?))
+    }
+
+    /// Start tracing the connections of clients built with `connection_verbose`,
+    /// until the guard is dropped. Traced requests should not run concurrently.
+    pub(crate) fn begin(self: &Arc<Self>) -> HttpResult<TraceGuard> {
+        let installed = *LOGGER.get_or_init(|| {
+            let installed = log::set_logger(&WireLogger).is_ok();
+            if installed {
+                log::set_max_level(log::LevelFilter::Trace);
+            }
+            installed
+        });
+        if !installed {
+            return Err(HttpError::RequestError(
+                "Tracing needs to install the process logger, but another one is set".to_string(),
+            ));
+        }
+        *ACTIVE.lock().unwrap() = Some(self.clone());
+        Ok(TraceGuard(self.clone()))
+    }
+
+    /// Record bytes read or written by a connection
+    fn record(&self, conn: &str, direction: Direction, mut bytes: &[u8]) {
+        let mut streams = self.streams.lock().unwrap();
+        let mut writer = self.writer.lock().unwrap();
+        if !streams.keys().any(|(id, _)| id == conn) {
+            let _ = writeln!(writer, "== Connection {}", conn);
+        }
+        let stream = streams
+            .entry((conn.to_string(), direction))
+            .or_insert_with(|| Stream::Head(Vec::new()));
+
+        while !bytes.is_empty() {
+            match stream {
+                Stream::Head(head) => {
+                    head.extend_from_slice(bytes);
+                    bytes = &[];
+                    if !is_http1(head, direction) || head.len() > MAX_HEAD {
+                        let data = std::mem::take(head);
+                        write_section(&mut *writer, direction, "data", &data);
+                        *stream = Stream::Raw;
+                    } else if let Some(end) = find(head, b"\r\n\r\n") {
+                        let mut head = std::mem::take(head);
+                        let rest = head.split_off(end + 4);
+                        write_section(&mut *writer, direction, "header", &head);
+                        *stream = match body_length(&head, direction) {
+                            Some(0) => Stream::Head(Vec::new()),
+                            length => Stream::Body(length),
+                        };
+                        // Bytes after the head go through the loop again
+                        drop(writer);
+                        drop(streams);
+                        self.record(conn, direction, &rest);
+                        return;
+                    }
+                }
+                Stream::Body(Some(remaining)) => {
+                    let n = (*remaining).min(bytes.len());
+                    write_section(&mut *writer, direction, "data", &bytes[..n]);
+                    bytes = &bytes[n..];
+                    *remaining -= n;
+                    if *remaining == 0 {
+                        *stream = Stream::Head(Vec::new());
+                    }
+                }
+                Stream::Body(None) | Stream::Raw => {
+                    write_section(&mut *writer, direction, "data", bytes);
+                    bytes = &[];
+                }
+            }
+        }
+    }
+}
+
+/// Keeps a trace active while a request runs
+pub(crate) struct TraceGuard(Arc<Trace>);
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        let mut active = ACTIVE.lock().unwrap();
+        if active
+            .as_ref()
+            .is_some_and(|trace| Arc::ptr_eq(trace, &self.0))
+        {
+            *active = None;
+        }
+        let _ = self.0.writer.lock().unwrap().flush();
+    }
+}
+
+/// Logger receiving reqwest's connection logs
+struct WireLogger;
+
+impl log::Log for WireLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == WIRE_TARGET && ACTIVE.lock().unwrap().is_some()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target() != WIRE_TARGET {
+            return;
+        }
+        let Some(trace) = ACTIVE.lock().unwrap().clone() else {
+            return;
+        };
+        if let Some((conn, direction, bytes)) = parse_record(&record.args().to_string()) {
+            trace.record(&conn, direction, &bytes);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Parse a record like `0a1b2c3d write: b"GET / HTTP/1.1\r\n..."`
+fn parse_record(message: &str) -> Option<(String, Direction, Vec<u8>)> {
+    let (conn, rest) = message.split_once(' ')?;
+    let (kind, payload) = rest.split_once(": ")?;
+    let direction = if kind.starts_with("read") {
+        Direction::Recv
+    } else if kind.starts_with("write") {
+        Direction::Send
+    } else {
+        return None;
+    };
+    Some((conn.to_string(), direction, unescape(payload)))
+}
+
+/// Turn Rust byte string literals back into bytes. Vectored writes are logged
+/// as one literal per buffer, like `b"GET / HTTP/1.1\r\n"b"{}"`.
+fn unescape(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.bytes();
+    let mut in_literal = false;
+    while let Some(c) = chars.next() {
+        if c == b'"' {
+            in_literal = !in_literal;
+            continue;
+        }
+        if !in_literal {
+            continue;
+        }
+        if c != b'\\' {
+            bytes.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'0') => bytes.push(b'\0'),
+            Some(b'x') => {
+                let hex = [chars.next().unwrap_or(b'0'), chars.next().unwrap_or(b'0')];
+                let hex = std::str::from_utf8(&hex).unwrap_or("00");
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+            }
+            Some(c) => bytes.push(c),
+            None => bytes.push(b'\\'),
+        }
+    }
+    bytes
+}
+
+fn is_http1(head: &[u8], direction: Direction) -> bool {
+    match direction {
+        Direction::Send => !head.starts_with(b"PRI * HTTP/2"),
+        Direction::Recv => b"HTTP/".starts_with(&head[..head.len().min(5)]),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Length of the body following a head: `None` when it runs to the end of the
+/// connection or is chunked
+fn body_length(head: &[u8], direction: Direction) -> Option<usize> {
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.lines();
+    let first = lines.next().unwrap_or_default();
+    if direction == Direction::Recv {
+        let status = first.split(' ').nth(1).unwrap_or_default();
+        if status.starts_with('1') || status == "204" || status == "304" {
+            return Some(0);
+        }
+    }
+
+    let mut length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            return None;
+        }
+        if name.eq_ignore_ascii_case("content-length") {
+            length = value.trim().parse().ok();
+        }
+    }
+    match (length, direction) {
+        (Some(length), _) => Some(length),
+        // Requests without a length have no body
+        (None, Direction::Send) => Some(0),
+        (None, Direction::Recv) => None,
+    }
+}
+
+/// Write a section like curl: text as is, binary data as a hex dump
+fn write_section(writer: &mut dyn Write, direction: Direction, kind: &str, bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    let arrow = match direction {
+        Direction::Send => "=> Send",
+        Direction::Recv => "<= Recv",
+    };
+    let _ = writeln!(
+        writer,
+        "{} {}, {} bytes (0x{:x})",
+        arrow,
+        kind,
+        bytes.len(),
+        bytes.len()
+    );
+    let _ = match std::str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+            let text = text.replace("\r\n", "\n");
+            if text.ends_with('\n') {
+                write!(writer, "{}", text)
+            } else {
+                writeln!(writer, "{}", text)
+            }
+        }
+        _ => write!(writer, "{}", hex_dump(bytes)),
+    };
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        dump.push_str(&format!(
+            "{:04x}: {:<47}  {}\n",
+            i * 16,
+            hex.join(" "),
+            ascii
+        ));
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_client::HttpMethod;
+    use crate::core::http_request::HttpRequest;
+    use crate::core::test_server::serve;
+
+    /// Writer whose output the test can read
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_request() {
+        let port = serve(|_, stream| {
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 3\r\n\r\n\x00\x01\x02")
+                .unwrap();
+        })
+        .port();
+
+        let output = Output::default();
+        HttpRequest::new(
+            HttpMethod::Post,
+            &format!("http://127.0.0.1:{}/items", port),
+        )
+        .header("Content-Type", "application/json")
+        .body(r#"{"name":"a"}"#)
+        .trace(Some(Arc::new(Trace::new(output.clone()))))
+        .send()
+        .await
+        .unwrap();
+
+        let trace = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let sections: Vec<&str> = trace
+            .lines()
+            .filter(|line| line.starts_with("=>") || line.starts_with("<="))
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                "=> Send header",
+                "=> Send data",
+                "<= Recv header",
+                "<= Recv data"
+            ]
+        );
+        assert!(trace.contains("=> Send data, 12 bytes (0xc)\n"));
+        assert!(trace.contains("<= Recv header, 38 bytes (0x26)\n"));
+        assert!(trace.starts_with("== Connection "));
+        assert!(trace.contains("POST /items HTTP/1.1\ncontent-type: application/json\n"));
+        assert!(trace.contains("content-length: 12\n"));
+        assert!(trace.contains("\n{\"name\":\"a\"}\n"));
+        assert!(trace.contains("0000: 00 01 02"));
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let (conn, direction, bytes) =
+            parse_record(r#"0a1b2c3d read: b"HTTP/1.1 200 OK\r\n\x00\\\"""#).unwrap();
+        assert_eq!(conn, "0a1b2c3d");
+        assert_eq!(direction, Direction::Recv);
+        assert_eq!(bytes, b"HTTP/1.1 200 OK\r\n\x00\\\"");
+        let (_, direction, bytes) =
+            parse_record(r#"0a1b2c3d write (vectored): b"a\tb"b"b\"d""#).unwrap();
+        assert_eq!(direction, Direction::Send);
+        assert_eq!(bytes, b"a\tbb\"d");
+        assert_eq!(parse_record("not a record"), None);
+        assert_eq!(parse_record(r#"0a1b2c3d flush: b"x""#), None);
+        assert_eq!(parse_record(r#"0a1b2c3d read b"x""#), None);
+
+        // A trailing backslash is kept and an invalid hex escape reads as zero
+        let (_, _, bytes) = parse_record(r#"0a1b2c3d read: b"ab\"#).unwrap();
+        assert_eq!(bytes, b"ab\\");
+        let (_, _, bytes) = parse_record(r#"0a1b2c3d read: b"\xzz!""#).unwrap();
+        assert_eq!(bytes, b"\0!");
+    }
+
+    #[test]
+    fn test_open_trace_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing").join("trace.txt");
+        let error = Trace::open(missing.to_str().unwrap()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(Trace::open(dir.path().to_str().unwrap()).is_err());
+    }
+}
@@ -1,6 +1,17 @@
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use reqwest::header::HeaderMap;
+use url::{form_urlencoded, Url};
+
+/// Characters escaped in path parameter values and query parameters (everything
+/// but RFC 3986 unreserved), the way SigV4 signing canonicalizes them
+pub const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
 
 /// Merge headers, replacing existing ones and removing those with empty values
 pub fn merge_headers(
@@ -34,3 +45,379 @@ pub fn build_header_map(headers: &[(String, String)]) -> HeaderMap {
     }
     header_map
 }
+
+/// Merge query parameters, keeping their order.
+///
+/// Keys present in `overrides` replace the defaults with the same key, and an
+/// empty override value removes the default. Repeated keys in `overrides` are kept.
+pub fn merge_params(
+    defaults: &[(String, String)],
+    overrides: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut merged: Vec<(String, String)> = defaults
+        .iter()
+        .filter(|(key, _)| !overrides.iter().any(|(k, _)| k == key))
+        .cloned()
+        .collect();
+    for (key, value) in overrides {
+        let is_default = defaults.iter().any(|(k, _)| k == key);
+        if !(value.is_empty() && is_default) {
+            merged.push((key.clone(), value.clone()));
+        }
+    }
+    merged
+}
+
+/// Join a collection base URL and an endpoint path
+pub fn join_url(base: &str, path: &str) -> String {
+    if base.ends_with('/') && path.starts_with('/') {
+        format!("{}{}", base, &path[1..])
+    } else {
+        format!("{}{}", base, path)
+    }
+}
+
+fn is_param_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Find the first character matching `pred` at or after `from`, skipping
+/// `{{...}}` template expressions
+fn find_outside_templates(url: &str, from: usize, pred: impl Fn(char) -> bool) -> Option<usize> {
+    let mut i = from;
+    while i < url.len() {
+        let tail = &url[i..];
+        if tail.starts_with("{{") {
+            i += tail.find("}}").map_or(tail.len(), |end| end + 2);
+            continue;
+        }
+        let c = tail.chars().next()?;
+        if pred(c) {
+            return Some(i);
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// Byte range of the path in a URL: after the `scheme://authority` prefix, if
+/// any, and before the query string or fragment
+fn path_range(url: &str) -> (usize, usize) {
+    let start = match url.find("://") {
+        Some(i) => find_outside_templates(url, i + 3, |c| matches!(c, '/' | '?' | '#'))
+            .unwrap_or(url.len()),
+        None => 0,
+    };
+    let end = find_outside_templates(url, start, |c| matches!(c, '?' | '#')).unwrap_or(url.len());
+    (start, end)
+}
+
+/// Walk the `{name}` path parameters of a URL, replacing each one with the value
+/// returned by `resolve`, or leaving it in place when `resolve` returns `None`.
+/// Only the path is searched, and `{{...}}` template expressions are skipped.
+fn replace_path_params<F>(url: &str, mut resolve: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let (path_start, path_end) = path_range(url);
+    let mut out = String::with_capacity(url.len());
+    out.push_str(&url[..path_start]);
+    let mut rest = &url[path_start..path_end];
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if tail.starts_with("{{") {
+            let end = tail.find("}}").map_or(tail.len(), |i| i + 2);
+            out.push_str(&tail[..end]);
+            rest = &tail[end..];
+            continue;
+        }
+        match tail[1..].find('}') {
+            Some(len) if is_param_name(&tail[1..=len]) => {
+                match resolve(&tail[1..=len]) {
+                    Some(value) => out.push_str(&value),
+                    None => out.push_str(&tail[..len + 2]),
+                }
+                rest = &tail[len + 2..];
+            }
+            _ => {
+                out.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.push_str(&url[path_end..]);
+    out
+}
+
+/// Get the names of the `{name}` path parameters in a URL, in order of appearance
+pub fn path_param_names(url: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    replace_path_params(url, |name| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        None
+    });
+    names
+}
+
+/// Replace `{name}` path parameters with their percent-encoded values.
+///
+/// Returns the filled URL and the names of the parameters without a value,
+/// which are left in place.
+pub fn fill_path_params(url: &str, params: &[(String, String)]) -> (String, Vec<String>) {
+    let mut missing: Vec<String> = Vec::new();
+    let filled = replace_path_params(url, |name| {
+        match params.iter().rev().find(|(key, _)| key == name) {
+            Some((_, value)) => Some(utf8_percent_encode(value, URI_COMPONENT).to_string()),
+            None => {
+                if !missing.iter().any(|n| n == name) {
+                    missing.push(name.to_string());
+                }
+                None
+            }
+        }
+    });
+    (filled, missing)
+}
+
+/// Append percent-encoded query parameters to a URL, spaces encoded as `%20`
+pub fn build_url(url: &str, query: &[(String, String)]) -> Result<String, url::ParseError> {
+    let mut url = Url::parse(url)?;
+    if !query.is_empty() {
+        let appended = query
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}={}",
+                    utf8_percent_encode(key, URI_COMPONENT),
+                    utf8_percent_encode(value, URI_COMPONENT)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let query = match url.query() {
+            Some(existing) if !existing.is_empty() => format!("{}&{}", existing, appended),
+            _ => appended,
+        };
+        url.set_query(Some(&query));
+    }
+    Ok(url.to_string())
+}
+
+/// Split a `unix:///path/to/app.sock:/base/path` URL into the socket path and
+/// the `http://localhost/base/path` URL sent over it
+pub fn split_unix_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("unix://")?;
+    let (socket, path) = match rest.find(":/") {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => (rest, "/"),
+    };
+    Some((socket.to_string(), format!("http://localhost{}", path)))
+}
+
+/// Current Unix time in seconds
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Split a Unix timestamp (seconds) into a UTC date and time:
+/// `(year, month, day, hour, minute, second)`
+pub fn utc_date_time(secs: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month,
+        day,
+        (rem / 3_600) as u32,
+        (rem % 3_600 / 60) as u32,
+        (rem % 60) as u32,
+    )
+}
+
+/// Parse an HTTP date like `Sun, 06 Nov 1994 08:49:37 GMT` into a Unix
+/// timestamp (seconds)
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    let [_, day, month, year, time, zone] = date
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .try_into()
+        .ok()?;
+    if zone != "GMT" {
+        return None;
+    }
+    let month = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ]
+    .iter()
+    .position(|m| *m == month)? as i64
+        + 1;
+    let day: i64 = day.parse().ok()?;
+    let year: i64 = year.parse().ok()?;
+    let time = time
+        .split(':')
+        .map(|part| part.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let [hour, minute, second] = time[..] else {
+        return None;
+    };
+
+    // Days from civil, the inverse of `utc_date_time`
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    u64::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+/// Encode key-value pairs as an `application/x-www-form-urlencoded` body
+pub fn encode_form(pairs: &[(String, String)]) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+/// Encode bytes as lowercase hexadecimal
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_params() {
+        let defaults = pairs(&[("page", "1"), ("lang", "en"), ("debug", "true")]);
+        let overrides = pairs(&[("lang", "ar"), ("tag", "a"), ("tag", "b"), ("debug", "")]);
+
+        let merged = merge_params(&defaults, &overrides);
+
+        assert_eq!(
+            merged,
+            pairs(&[("page", "1"), ("lang", "ar"), ("tag", "a"), ("tag", "b")])
+        );
+    }
+
+    #[test]
+    fn test_split_unix_url() {
+        assert_eq!(
+            split_unix_url(&join_url("unix:///var/run/app.sock:/v1/", "/users?page=2")),
+            Some((
+                "/var/run/app.sock".to_string(),
+                "http://localhost/v1/users?page=2".to_string()
+            ))
+        );
+        assert_eq!(
+            split_unix_url("unix:///tmp/docker.sock"),
+            Some((
+                "/tmp/docker.sock".to_string(),
+                "http://localhost/".to_string()
+            ))
+        );
+        assert_eq!(split_unix_url("http://localhost:8080/v1"), None);
+    }
+
+    #[test]
+    fn test_build_url_encodes_query() {
+        let query = pairs(&[("q", "a b&c"), ("name", "é")]);
+
+        let url = build_url("http://localhost:8080/search?x=1", &query).unwrap();
+
+        assert_eq!(
+            url,
+            "http://localhost:8080/search?x=1&q=a%20b%26c&name=%C3%A9"
+        );
+        assert_eq!(
+            join_url("http://localhost:8080/", "/users"),
+            "http://localhost:8080/users"
+        );
+    }
+
+    #[test]
+    fn test_fill_path_params() {
+        let url = "http://localhost/users/{userId}/orders/{orderId}?x={{$uuid}}";
+        assert_eq!(path_param_names(url), vec!["userId", "orderId"]);
+
+        let (filled, missing) = fill_path_params(url, &pairs(&[("userId", "a b/c")]));
+        assert_eq!(
+            filled,
+            "http://localhost/users/a%20b%2Fc/orders/{orderId}?x={{$uuid}}"
+        );
+        assert_eq!(missing, vec!["orderId"]);
+
+        let (filled, missing) =
+            fill_path_params("/{ not a param}/{id}", &pairs(&[("id", "1"), ("id", "42")]));
+        assert_eq!(filled, "/{ not a param}/42");
+        assert!(missing.is_empty());
+
+        let url = "http://{host}:8080/users/{id}?q={id}#{id}";
+        assert_eq!(path_param_names(url), vec!["id"]);
+        let (filled, _) = fill_path_params(url, &pairs(&[("id", "7"), ("host", "x")]));
+        assert_eq!(filled, "http://{host}:8080/users/7?q={id}#{id}");
+
+        let (filled, _) = fill_path_params(
+            "{{base}}/items/{id}/{{? note}}?a={id}",
+            &pairs(&[("id", "7")]),
+        );
+        assert_eq!(filled, "{{base}}/items/7/{{? note}}?a={id}");
+    }
+
+    #[test]
+    fn test_utc_date_time() {
+        assert_eq!(utc_date_time(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(utc_date_time(1_440_938_160), (2015, 8, 30, 12, 36, 0));
+        assert_eq!(utc_date_time(1_709_210_096), (2024, 2, 29, 12, 34, 56));
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 12:34:56 GMT"),
+            Some(1_709_210_096)
+        );
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 PST"), None);
+        assert_eq!(parse_http_date("120"), None);
+    }
+
+    #[test]
+    fn test_encode_form() {
+        let form = pairs(&[("user", "john doe"), ("redirect", "/home?x=1")]);
+
+        assert_eq!(encode_form(&form), "user=john+doe&redirect=%2Fhome%3Fx%3D1");
+    }
+}
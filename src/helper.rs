@@ -2,7 +2,7 @@ use std::{
     env,
     fs::File,
     io::{self, Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use std::sync::OnceLock;
@@ -26,7 +26,20 @@ pub fn coman_json() -> &'static str {
     CACHE.get_or_init(|| env::var("COMAN_JSON").unwrap_or_else(|_| COMAN_FILE.to_string()))
 }
 
+static DATA_FILE: OnceLock<String> = OnceLock::new();
+
+/// Use `path` as the data file when `COMAN_JSON` is not set. Only the first call
+/// has an effect, and only before the first [`get_file_path`].
+pub fn set_data_file(path: String) {
+    let _ = DATA_FILE.set(path);
+}
+
 pub fn get_file_path() -> &'static str {
+    #[cfg(test)]
+    if let Some(path) = *tests::DATA_FILE_OVERRIDE.lock().unwrap() {
+        return path;
+    }
+
     static CACHE: OnceLock<&'static str> = OnceLock::new();
 
     CACHE.get_or_init(|| {
@@ -34,6 +47,8 @@ pub fn get_file_path() -> &'static str {
         // If env var was set (different from default), use it directly as full path
         if json_path != COMAN_FILE {
             json_path
+        } else if let Some(data_file) = DATA_FILE.get() {
+            data_file
         } else {
             // Leak the formatted string to get &'static str
             Box::leak(format!("{}/{}", home_dir(), json_path).into_boxed_str())
@@ -41,6 +56,33 @@ pub fn get_file_path() -> &'static str {
     })
 }
 
+/// Directory holding coman state files (prompt history, caches), next to the data file
+pub fn state_dir() -> PathBuf {
+    Path::new(get_file_path())
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(".coman")
+}
+
+/// Reads a JSON state file from the state directory, falling back to the default value
+/// when the file does not exist or cannot be parsed.
+pub fn read_state<T: serde::de::DeserializeOwned + Default>(name: &str) -> T {
+    std::fs::read_to_string(state_dir().join(name))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Atomically writes a JSON state file to the state directory
+pub fn write_state<T: serde::Serialize + ?Sized>(
+    name: &str,
+    data: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = state_dir();
+    std::fs::create_dir_all(&dir)?;
+    write_json_to_path(&dir.join(name), data)
+}
+
 /// Atomically writes JSON data to file with file locking.
 ///
 /// This function:
@@ -51,21 +93,29 @@ pub fn get_file_path() -> &'static str {
 pub fn write_json_to_file<T: serde::Serialize + ?Sized>(
     data: &T,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let file_path = get_file_path();
-    let path = Path::new(&file_path);
-
-    // Get parent directory for temp file (must be on same filesystem for atomic rename)
-    let parent_dir = path.parent().unwrap_or(Path::new("."));
+    write_json_to_path(Path::new(get_file_path()), data)
+}
 
-    // Use a closure to ensure lock is released even on error
+/// Atomically writes JSON data to the given path
+pub fn write_json_to_path<T: serde::Serialize + ?Sized>(
+    path: &Path,
+    data: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Serialize data
     let json = serde_json::to_string_pretty(data)?;
+    write_to_path(path, json.as_bytes())
+}
+
+/// Atomically writes bytes to the given path, through a temp file renamed over it
+pub fn write_to_path(path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    // Get parent directory for temp file (must be on same filesystem for atomic rename)
+    let parent_dir = path.parent().unwrap_or(Path::new("."));
 
     // Create temp file in the same directory (required for atomic rename)
     let mut temp_file = NamedTempFile::new_in(parent_dir)?;
 
-    // Write JSON to temp file
-    temp_file.write_all(json.as_bytes())?;
+    // Write contents to temp file
+    temp_file.write_all(contents)?;
     temp_file.flush()?;
 
     // Sync to disk to ensure durability
@@ -73,7 +123,7 @@ pub fn write_json_to_file<T: serde::Serialize + ?Sized>(
 
     // Atomically rename temp file to target (this is the atomic operation)
     // persist() consumes the temp file and prevents auto-deletion
-    temp_file.persist(file_path)?;
+    temp_file.persist(path)?;
 
     Ok(())
 }
@@ -118,8 +168,37 @@ pub fn confirm(prompt: &str) -> bool {
 #[cfg(test)]
 pub mod tests {
 
+    use std::sync::Mutex;
+
     use serial_test::serial;
 
+    /// Data file path returned by [`super::get_file_path`] in place of the cached one
+    pub static DATA_FILE_OVERRIDE: Mutex<Option<&'static str>> = Mutex::new(None);
+
+    /// Data file in a temporary directory used by [`super::get_file_path`], so that
+    /// state files are written there. The previous override is restored on drop.
+    pub struct TempDataFile {
+        pub dir: tempfile::TempDir,
+        previous: Option<&'static str>,
+    }
+
+    /// Point the data file at a new temporary directory
+    pub fn temp_data_file() -> TempDataFile {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.json").to_string_lossy().into_owned();
+        let previous = DATA_FILE_OVERRIDE
+            .lock()
+            .unwrap()
+            .replace(Box::leak(path.into_boxed_str()));
+        TempDataFile { dir, previous }
+    }
+
+    impl Drop for TempDataFile {
+        fn drop(&mut self) {
+            *DATA_FILE_OVERRIDE.lock().unwrap() = self.previous;
+        }
+    }
+
     #[test]
     #[serial]
     fn test_serial_01_read_write_json_from_file() {
@@ -146,4 +225,15 @@ pub mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    #[serial]
+    fn test_state_dir_follows_data_file() {
+        let data_file = temp_data_file();
+        assert_eq!(super::state_dir(), data_file.dir.path().join(".coman"));
+
+        super::write_state("state-test.json", &vec![1, 2]).unwrap();
+        let state: Vec<i32> = super::read_state("state-test.json");
+        assert_eq!(state, vec![1, 2]);
+    }
 }
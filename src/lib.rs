@@ -11,8 +11,8 @@ pub mod models;
 pub use core::collection_manager::CollectionManager;
 pub use core::http_client::{HttpClient, HttpMethod, HttpResult};
 pub use core::http_request::HttpRequest;
-pub use core::http_response::HttpResponse;
-pub use models::collection::{Collection, Method, Request};
+pub use core::http_response::{Attempt, HttpResponse, HttpVersion, Redirect, Timings};
+pub use models::collection::{Auth, Collection, FormPart, Method, Request};
 
 // CLI module (only available with the cli feature)
 #[cfg(feature = "cli")]
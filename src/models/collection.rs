@@ -1,11 +1,13 @@
 use core::fmt;
+use std::net::IpAddr;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
 #[serde(try_from = "String")]
 pub enum Method {
+    #[default]
     Get,
     Post,
     Put,
@@ -13,11 +15,39 @@ pub enum Method {
     Patch,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Collection {
     pub name: String,
     pub url: String,
     pub headers: Vec<(String, String)>,
+    /// Default query parameters for all endpoints in the collection
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<(String, String)>,
+    /// Authentication inherited by all endpoints in the collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// Keep a cookie jar for the collection, persisted between runs
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cookies: bool,
+    /// TLS settings for all endpoints in the collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// Proxy for all endpoints in the collection, replacing the environment proxies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// Unix domain socket the endpoints are reached through, the URL host only
+    /// naming the `Host` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket: Option<String>,
+    /// DNS overrides and connection settings for all endpoints in the collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect: Option<ConnectConfig>,
+    /// HTTP version for all endpoints in the collection, instead of negotiating it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<HttpProtocol>,
+    /// Timeouts and redirects for all endpoints in the collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<RequestSettings>,
     pub requests: Option<Vec<Request>>,
 }
 
@@ -30,15 +60,463 @@ impl Collection {
             None
         }
     }
+
+    /// Get the authentication for a request: the endpoint auth overrides the
+    /// collection auth, and `Auth::None` disables it.
+    pub fn request_auth(&self, request: &Request) -> Option<Auth> {
+        match request.auth.as_ref().or(self.auth.as_ref()) {
+            Some(Auth::None) | None => None,
+            Some(auth) => Some(auth.clone()),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Request {
     pub name: String,
     pub endpoint: String,
     pub method: Method,
     pub headers: Vec<(String, String)>,
     pub body: Option<String>,
+    /// Query parameters, merged over the collection defaults
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<(String, String)>,
+    /// Default values for `{name}` path parameters in the endpoint
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<(String, String)>,
+    /// Multipart form body, sent instead of `body` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multipart: Option<Vec<FormPart>>,
+    /// `application/x-www-form-urlencoded` body, sent instead of `body` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub urlencoded: Option<Vec<(String, String)>>,
+    /// Authentication, overriding the collection auth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// Retries of failed attempts, for flaky endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Timeouts and redirects, overriding the collection ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<RequestSettings>,
+}
+
+/// Authentication applied to requests
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    /// No authentication, disables the auth inherited from the collection
+    None,
+    /// HTTP Basic authentication
+    Basic { username: String, password: String },
+    /// Bearer token in the `Authorization` header
+    Bearer { token: String },
+    /// API key sent in a header or query parameter
+    ApiKey {
+        key: String,
+        value: String,
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+    /// OAuth 2.0 access token, fetched from the token endpoint and cached
+    OAuth2(OAuth2),
+    /// AWS Signature Version 4, signed when the request is sent
+    #[serde(rename = "sigv4")]
+    AwsSigV4(AwsSigV4),
+    /// HMAC signature header over a canonical string, signed when the request is sent
+    Hmac(HmacAuth),
+    /// JSON Web Token, minted for every request
+    Jwt(JwtAuth),
+    /// HTTP Digest authentication, answering the server's challenge
+    Digest(DigestAuth),
+}
+
+/// HTTP Digest credentials. The realm, nonce and algorithm come from the server challenge.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DigestAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// HMAC request signing settings
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct HmacAuth {
+    pub secret: String,
+    #[serde(default)]
+    pub algorithm: HmacAlgorithm,
+    /// Header carrying the signature
+    #[serde(default = "HmacAuth::default_header")]
+    pub header: String,
+    /// Signed string, with `{timestamp}`, `{method}`, `{path}`, `{query}`, `{url}`
+    /// and `{body}` variables and `\n` for newlines
+    #[serde(default = "HmacAuth::default_template")]
+    pub template: String,
+    /// Header carrying the timestamp used in the signature, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_header: Option<String>,
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+    /// Prefix of the signature header value, e.g. `sha256=`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prefix: String,
+}
+
+impl HmacAuth {
+    fn default_header() -> String {
+        "X-Signature".to_string()
+    }
+
+    fn default_template() -> String {
+        "{timestamp}.{body}".to_string()
+    }
+}
+
+impl Default for HmacAuth {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            algorithm: HmacAlgorithm::default(),
+            header: Self::default_header(),
+            template: Self::default_template(),
+            timestamp_header: Some("X-Timestamp".to_string()),
+            encoding: SignatureEncoding::default(),
+            prefix: String::new(),
+        }
+    }
+}
+
+/// Hash function of an HMAC signature
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HmacAlgorithm {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+/// Text encoding of a signature
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// JSON Web Token minting settings
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct JwtAuth {
+    /// Shared secret for HS algorithms, PEM private key for RS256
+    pub key: String,
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    /// JSON object of claims; `iat` and `exp` are added unless present
+    #[serde(default = "JwtAuth::default_claims")]
+    pub claims: String,
+    /// Lifetime of the token in seconds
+    #[serde(default = "JwtAuth::default_ttl")]
+    pub ttl: u64,
+    /// Header carrying the token
+    #[serde(default = "JwtAuth::default_header")]
+    pub header: String,
+    /// Prefix of the header value
+    #[serde(default = "JwtAuth::default_prefix")]
+    pub prefix: String,
+}
+
+impl JwtAuth {
+    fn default_claims() -> String {
+        "{}".to_string()
+    }
+
+    fn default_ttl() -> u64 {
+        300
+    }
+
+    fn default_header() -> String {
+        "Authorization".to_string()
+    }
+
+    fn default_prefix() -> String {
+        "Bearer ".to_string()
+    }
+}
+
+impl Default for JwtAuth {
+    fn default() -> Self {
+        Self {
+            key: String::new(),
+            algorithm: JwtAlgorithm::default(),
+            claims: Self::default_claims(),
+            ttl: Self::default_ttl(),
+            header: Self::default_header(),
+            prefix: Self::default_prefix(),
+        }
+    }
+}
+
+/// JWT signing algorithm
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub enum JwtAlgorithm {
+    #[default]
+    HS256,
+    HS384,
+    HS512,
+    RS256,
+}
+
+/// AWS Signature Version 4 credentials and scope
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct AwsSigV4 {
+    pub access_key: String,
+    pub secret_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    pub region: String,
+    pub service: String,
+}
+
+/// OAuth 2.0 token endpoint settings
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct OAuth2 {
+    pub token_url: String,
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub client_secret: String,
+    #[serde(default)]
+    pub grant: OAuth2Grant,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Resource owner username, for the password grant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Resource owner password, for the password grant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Refresh token, for the refresh_token grant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Authorization endpoint, for the authorization_code grant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorize_url: Option<String>,
+    /// Loopback redirect URI, for the authorization_code grant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+}
+
+/// OAuth 2.0 grant used to obtain an access token
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuth2Grant {
+    #[default]
+    ClientCredentials,
+    Password,
+    RefreshToken,
+    AuthorizationCode,
+}
+
+/// Where an API key is sent
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// TLS settings of a collection or a single command
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TlsConfig {
+    /// PEM file of CA certificates trusted in addition to the system roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// Skip certificate and host name verification
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub insecure: bool,
+    /// Client certificate: a PEM file, with `client_key` or its key included,
+    /// or a PKCS#12 archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<String>,
+    /// PEM file of the client certificate private key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    /// Password of a PKCS#12 client certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_password: Option<String>,
+    /// Lowest TLS version accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<TlsVersion>,
+}
+
+/// TLS protocol version
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls1_0,
+    #[serde(rename = "1.1")]
+    Tls1_1,
+    #[serde(rename = "1.2")]
+    Tls1_2,
+    #[serde(rename = "1.3")]
+    Tls1_3,
+}
+
+/// HTTP version requests are made with, instead of negotiating it
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub enum HttpProtocol {
+    /// Only HTTP/1.1, even when the server offers HTTP/2
+    #[serde(rename = "http1.1")]
+    Http1_1,
+    /// HTTP/2 without negotiation, also over plain HTTP
+    #[serde(rename = "http2-prior-knowledge")]
+    Http2PriorKnowledge,
+}
+
+/// Timeouts and redirects of a collection or an endpoint
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RequestSettings {
+    /// Timeout of the whole request in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Timeout of opening the connection in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_ms: Option<u64>,
+    /// Follow redirects instead of returning them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+    /// Most redirects followed before failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+}
+
+/// Retries of requests failing with a connection error, a timeout or one of
+/// the retried statuses, waiting longer before each one
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RetryPolicy {
+    /// Attempts in all, the first one included
+    pub max_attempts: u32,
+    /// Response statuses retried
+    #[serde(default = "RetryPolicy::default_statuses")]
+    pub statuses: Vec<u16>,
+    /// Delay before the first retry in milliseconds, doubled for each next one
+    /// and jittered
+    #[serde(default = "RetryPolicy::default_delay_ms")]
+    pub delay_ms: u64,
+    /// Longest delay in milliseconds, also capping `Retry-After`
+    #[serde(default = "RetryPolicy::default_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    fn default_statuses() -> Vec<u16> {
+        vec![429, 502, 503, 504]
+    }
+
+    fn default_delay_ms() -> u64 {
+        500
+    }
+
+    fn default_max_delay_ms() -> u64 {
+        30_000
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            statuses: Self::default_statuses(),
+            delay_ms: Self::default_delay_ms(),
+            max_delay_ms: Self::default_max_delay_ms(),
+        }
+    }
+}
+
+/// Proxy the requests are sent through
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ProxyConfig {
+    /// Proxy URL with an http, https, socks4, socks4a, socks5 or socks5h scheme, or `none` to
+    /// connect directly, ignoring the environment proxies
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Hosts, domains, IP addresses or CIDR ranges reached without the proxy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+/// DNS overrides and connection settings of a collection or a single command
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ConnectConfig {
+    /// Addresses used for host names instead of resolving them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolve: Vec<ResolveRule>,
+    /// Other hosts or ports connected to, keeping the `Host` header and TLS name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connect_to: Vec<ConnectTo>,
+    /// Only connect to addresses of this IP version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_version: Option<IpVersion>,
+    /// Local IP address connections are made from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_address: Option<IpAddr>,
+}
+
+/// DNS override `HOST:PORT:ADDRESS[,ADDRESS...]`, with `*` for any port
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct ResolveRule {
+    pub host: String,
+    pub port: Option<u16>,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// Connection redirect `HOST1:PORT1:HOST2:PORT2`, where an empty field matches
+/// any host or port, or keeps the original one
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct ConnectTo {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub target_host: Option<String>,
+    pub target_port: Option<u16>,
+}
+
+/// IP version of the addresses connected to
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum IpVersion {
+    Ipv4,
+    Ipv6,
+}
+
+/// A single named part of a multipart/form-data body
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FormPart {
+    /// Plain text field
+    Text { name: String, value: String },
+    /// File field, read from `path` when the request is sent
+    File {
+        name: String,
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+    },
+}
+
+impl FormPart {
+    /// Get the field name of the part
+    pub fn name(&self) -> &str {
+        match self {
+            FormPart::Text { name, .. } | FormPart::File { name, .. } => name,
+        }
+    }
 }
 
 impl fmt::Display for Method {
@@ -71,3 +549,827 @@ impl FromStr for Method {
         }
     }
 }
+
+impl fmt::Display for FormPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormPart::Text { name, value } => write!(f, "{}={}", name, value),
+            FormPart::File {
+                name,
+                path,
+                content_type,
+                filename,
+            } => {
+                write!(f, "{}=@{}", name, path)?;
+                if let Some(content_type) = content_type {
+                    write!(f, ";type={}", content_type)?;
+                }
+                if let Some(filename) = filename {
+                    write!(f, ";filename={}", filename)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::None => write!(f, "none"),
+            Auth::Basic { username, password } => write!(f, "basic:{}:{}", username, password),
+            Auth::Bearer { token } => write!(f, "bearer:{}", token),
+            Auth::ApiKey {
+                key,
+                value,
+                location,
+            } => write!(f, "apikey:{}:{}:{}", location, key, value),
+            Auth::OAuth2(oauth) => write!(f, "{}", oauth),
+            Auth::AwsSigV4(aws) => {
+                write!(
+                    f,
+                    "sigv4:{}:{}:{}:{}",
+                    aws.access_key, aws.secret_key, aws.region, aws.service
+                )?;
+                if let Some(token) = &aws.session_token {
+                    write!(f, ":{}", token)?;
+                }
+                Ok(())
+            }
+            Auth::Hmac(hmac) => write!(f, "{}", hmac),
+            Auth::Jwt(jwt) => write!(f, "{}", jwt),
+            Auth::Digest(digest) => write!(f, "digest:{}:{}", digest.username, digest.password),
+        }
+    }
+}
+
+impl fmt::Display for OAuth2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "oauth2:grant={};token_url={};client_id={}",
+            self.grant, self.token_url, self.client_id
+        )?;
+        if !self.client_secret.is_empty() {
+            write!(f, ";client_secret={}", self.client_secret)?;
+        }
+        let options = [
+            ("scope", &self.scope),
+            ("username", &self.username),
+            ("password", &self.password),
+            ("refresh_token", &self.refresh_token),
+            ("authorize_url", &self.authorize_url),
+            ("redirect_uri", &self.redirect_uri),
+        ];
+        for (name, value) in options {
+            if let Some(value) = value {
+                write!(f, ";{}={}", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for OAuth2Grant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OAuth2Grant::ClientCredentials => write!(f, "client_credentials"),
+            OAuth2Grant::Password => write!(f, "password"),
+            OAuth2Grant::RefreshToken => write!(f, "refresh_token"),
+            OAuth2Grant::AuthorizationCode => write!(f, "authorization_code"),
+        }
+    }
+}
+
+impl FromStr for OAuth2 {
+    type Err = String;
+
+    /// Parse OAuth2 settings: `token_url=URL;client_id=ID[;client_secret=SECRET][;grant=GRANT]
+    /// [;scope=SCOPE][;username=USER;password=PASSWORD][;refresh_token=TOKEN]
+    /// [;authorize_url=URL][;redirect_uri=URI]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut oauth = OAuth2::default();
+        for (name, value) in parse_options(s, "OAuth2")? {
+            match name {
+                "token_url" => oauth.token_url = value,
+                "client_id" => oauth.client_id = value,
+                "client_secret" => oauth.client_secret = value,
+                "scope" => oauth.scope = Some(value),
+                "username" => oauth.username = Some(value),
+                "password" => oauth.password = Some(value),
+                "refresh_token" => oauth.refresh_token = Some(value),
+                "authorize_url" => oauth.authorize_url = Some(value),
+                "redirect_uri" => oauth.redirect_uri = Some(value),
+                "grant" => {
+                    oauth.grant = match value.as_str() {
+                        "client_credentials" => OAuth2Grant::ClientCredentials,
+                        "password" => OAuth2Grant::Password,
+                        "refresh_token" => OAuth2Grant::RefreshToken,
+                        "authorization_code" => OAuth2Grant::AuthorizationCode,
+                        _ => {
+                            return Err(format!(
+                                "Invalid OAuth2 grant '{}'. Use client_credentials, password, refresh_token or authorization_code",
+                                value
+                            ))
+                        }
+                    }
+                }
+                _ => return Err(format!("Unknown OAuth2 option '{}'", name)),
+            }
+        }
+        if oauth.token_url.is_empty() || oauth.client_id.is_empty() {
+            return Err("OAuth2 requires token_url and client_id".to_string());
+        }
+        match oauth.grant {
+            OAuth2Grant::Password if oauth.username.is_none() || oauth.password.is_none() => {
+                Err("The OAuth2 password grant requires username and password".to_string())
+            }
+            OAuth2Grant::RefreshToken if oauth.refresh_token.is_none() => {
+                Err("The OAuth2 refresh_token grant requires refresh_token".to_string())
+            }
+            OAuth2Grant::AuthorizationCode if oauth.authorize_url.is_none() => {
+                Err("The OAuth2 authorization_code grant requires authorize_url".to_string())
+            }
+            _ => Ok(oauth),
+        }
+    }
+}
+
+impl fmt::Display for ApiKeyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiKeyLocation::Header => write!(f, "header"),
+            ApiKeyLocation::Query => write!(f, "query"),
+        }
+    }
+}
+
+impl fmt::Display for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = Vec::new();
+        if self.insecure {
+            settings.push("insecure".to_string());
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            settings.push(format!("cacert={}", ca_cert));
+        }
+        if let Some(client_cert) = &self.client_cert {
+            settings.push(format!("cert={}", client_cert));
+        }
+        if let Some(client_key) = &self.client_key {
+            settings.push(format!("key={}", client_key));
+        }
+        if self.client_cert_password.is_some() {
+            settings.push("cert-password=****".to_string());
+        }
+        if let Some(min_version) = self.min_version {
+            settings.push(format!("min={}", min_version));
+        }
+        write!(f, "{}", settings.join(", "))
+    }
+}
+
+impl fmt::Display for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Credentials in the URL are masked like the password
+        match url::Url::parse(&self.url) {
+            Ok(mut url) if url.password().is_some() => {
+                let _ = url.set_password(Some("****"));
+                write!(f, "{}", url)?;
+            }
+            _ => write!(f, "{}", self.url)?,
+        }
+        if let Some(username) = &self.username {
+            write!(f, " (user {}", username)?;
+            if self.password.is_some() {
+                write!(f, ":****")?;
+            }
+            write!(f, ")")?;
+        }
+        if !self.no_proxy.is_empty() {
+            write!(f, " no proxy: {}", self.no_proxy.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConnectConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings: Vec<String> = Vec::new();
+        settings.extend(self.resolve.iter().map(|rule| format!("resolve {}", rule)));
+        settings.extend(
+            self.connect_to
+                .iter()
+                .map(|rule| format!("connect-to {}", rule)),
+        );
+        if let Some(ip_version) = self.ip_version {
+            settings.push(format!("{} only", ip_version));
+        }
+        if let Some(local_address) = self.local_address {
+            settings.push(format!("from {}", local_address));
+        }
+        write!(f, "{}", settings.join(", "))
+    }
+}
+
+impl fmt::Display for ResolveRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let addresses: Vec<String> = self.addresses.iter().map(|a| bracket_ip(*a)).collect();
+        write!(
+            f,
+            "{}:{}:{}",
+            self.host,
+            self.port.map_or("*".to_string(), |port| port.to_string()),
+            addresses.join(",")
+        )
+    }
+}
+
+impl From<ResolveRule> for String {
+    fn from(rule: ResolveRule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl TryFrom<String> for ResolveRule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for ResolveRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid resolve rule '{}'. Use HOST:PORT:ADDRESS[,ADDRESS]",
+                s
+            )
+        };
+        let [host, port, addresses] = split_fields::<3>(s).ok_or_else(invalid)?;
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let addresses = addresses
+            .split(',')
+            .map(|a| a.trim_start_matches('[').trim_end_matches(']').parse())
+            .collect::<Result<Vec<IpAddr>, _>>()
+            .map_err(|_| invalid())?;
+        Ok(ResolveRule {
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            port: if port == "*" {
+                None
+            } else {
+                Some(port.parse().map_err(|_| invalid())?)
+            },
+            addresses,
+        })
+    }
+}
+
+impl fmt::Display for ConnectTo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let port = |port: Option<u16>| port.map(|p| p.to_string()).unwrap_or_default();
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.host.as_deref().unwrap_or_default(),
+            port(self.port),
+            self.target_host.as_deref().unwrap_or_default(),
+            port(self.target_port)
+        )
+    }
+}
+
+impl From<ConnectTo> for String {
+    fn from(rule: ConnectTo) -> Self {
+        rule.to_string()
+    }
+}
+
+impl TryFrom<String> for ConnectTo {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for ConnectTo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid connect-to rule '{}'. Use HOST1:PORT1:HOST2:PORT2, where any field may be empty",
+                s
+            )
+        };
+        let [host, port, target_host, target_port] = split_fields::<4>(s).ok_or_else(invalid)?;
+        let host_field = |host: &str| {
+            Some(
+                host.trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string(),
+            )
+            .filter(|host| !host.is_empty())
+        };
+        let port_field = |port: &str| match port {
+            "" => Ok(None),
+            _ => port.parse().map(Some).map_err(|_| invalid()),
+        };
+        Ok(ConnectTo {
+            host: host_field(host),
+            port: port_field(port)?,
+            target_host: host_field(target_host),
+            target_port: port_field(target_port)?,
+        })
+    }
+}
+
+impl fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpVersion::Ipv4 => write!(f, "IPv4"),
+            IpVersion::Ipv6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+/// Split `N` colon-separated fields, where bracketed IPv6 addresses keep their colons
+fn split_fields<const N: usize>(s: &str) -> Option<[&str; N]> {
+    let mut fields = [""; N];
+    let mut rest = s;
+    for (index, field) in fields.iter_mut().enumerate() {
+        if index == N - 1 {
+            *field = rest;
+            break;
+        }
+        let end = if rest.starts_with('[') {
+            rest.find(']')? + 1
+        } else {
+            rest.find(':')?
+        };
+        *field = &rest[..end];
+        rest = rest[end..].strip_prefix(':')?;
+    }
+    Some(fields)
+}
+
+fn bracket_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
+    }
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsVersion::Tls1_0 => write!(f, "1.0"),
+            TlsVersion::Tls1_1 => write!(f, "1.1"),
+            TlsVersion::Tls1_2 => write!(f, "1.2"),
+            TlsVersion::Tls1_3 => write!(f, "1.3"),
+        }
+    }
+}
+
+impl FromStr for TlsVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_start_matches(['v', 'V']) {
+            "1.0" => Ok(TlsVersion::Tls1_0),
+            "1.1" => Ok(TlsVersion::Tls1_1),
+            "1.2" => Ok(TlsVersion::Tls1_2),
+            "1.3" => Ok(TlsVersion::Tls1_3),
+            _ => Err(format!(
+                "Invalid TLS version '{}': expected 1.0, 1.1, 1.2 or 1.3",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for HttpProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpProtocol::Http1_1 => write!(f, "HTTP/1.1"),
+            HttpProtocol::Http2PriorKnowledge => write!(f, "HTTP/2 prior knowledge"),
+        }
+    }
+}
+
+impl fmt::Display for RequestSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = |ms: u64| std::time::Duration::from_millis(ms).as_secs_f64();
+        let mut settings: Vec<String> = Vec::new();
+        if let Some(timeout) = self.timeout_ms {
+            settings.push(format!("timeout {} s", seconds(timeout)));
+        }
+        if let Some(timeout) = self.connect_timeout_ms {
+            settings.push(format!("connect timeout {} s", seconds(timeout)));
+        }
+        match self.follow_redirects {
+            Some(true) => settings.push("follow redirects".to_string()),
+            Some(false) => settings.push("no redirects".to_string()),
+            None => {}
+        }
+        if let Some(max) = self.max_redirects {
+            settings.push(format!("max {} redirects", max));
+        }
+        write!(f, "{}", settings.join(", "))
+    }
+}
+
+impl fmt::Display for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let statuses: Vec<String> = self.statuses.iter().map(|s| s.to_string()).collect();
+        write!(
+            f,
+            "{} attempts, statuses {}, delay {} ms up to {} ms",
+            self.max_attempts,
+            statuses.join(","),
+            self.delay_ms,
+            self.max_delay_ms
+        )
+    }
+}
+
+impl fmt::Display for HmacAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hmac:secret={};algorithm={};header={};template={};encoding={}",
+            self.secret, self.algorithm, self.header, self.template, self.encoding
+        )?;
+        if let Some(timestamp_header) = &self.timestamp_header {
+            write!(f, ";timestamp_header={}", timestamp_header)?;
+        }
+        if !self.prefix.is_empty() {
+            write!(f, ";prefix={}", self.prefix)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HmacAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HmacAlgorithm::Sha256 => write!(f, "sha256"),
+            HmacAlgorithm::Sha384 => write!(f, "sha384"),
+            HmacAlgorithm::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+impl fmt::Display for SignatureEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureEncoding::Hex => write!(f, "hex"),
+            SignatureEncoding::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+impl FromStr for HmacAuth {
+    type Err = String;
+
+    /// Parse HMAC settings: `secret=SECRET[;algorithm=sha256|sha384|sha512][;header=NAME]
+    /// [;template=TEMPLATE][;timestamp_header=NAME][;encoding=hex|base64][;prefix=PREFIX]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hmac = HmacAuth::default();
+        for (name, value) in parse_options(s, "HMAC")? {
+            match name {
+                "secret" => hmac.secret = value,
+                "header" => hmac.header = value,
+                "template" => hmac.template = value,
+                "timestamp_header" if value.is_empty() => hmac.timestamp_header = None,
+                "timestamp_header" => hmac.timestamp_header = Some(value),
+                "prefix" => hmac.prefix = value,
+                "algorithm" => {
+                    hmac.algorithm = match value.to_lowercase().as_str() {
+                        "sha256" => HmacAlgorithm::Sha256,
+                        "sha384" => HmacAlgorithm::Sha384,
+                        "sha512" => HmacAlgorithm::Sha512,
+                        _ => {
+                            return Err(format!(
+                                "Invalid HMAC algorithm '{}'. Use sha256, sha384 or sha512",
+                                value
+                            ))
+                        }
+                    }
+                }
+                "encoding" => {
+                    hmac.encoding = match value.to_lowercase().as_str() {
+                        "hex" => SignatureEncoding::Hex,
+                        "base64" => SignatureEncoding::Base64,
+                        _ => {
+                            return Err(format!(
+                                "Invalid HMAC encoding '{}'. Use hex or base64",
+                                value
+                            ))
+                        }
+                    }
+                }
+                _ => return Err(format!("Unknown HMAC option '{}'", name)),
+            }
+        }
+        if hmac.secret.is_empty() {
+            return Err("HMAC requires secret".to_string());
+        }
+        Ok(hmac)
+    }
+}
+
+impl fmt::Display for JwtAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "jwt:key={};algorithm={:?};claims={};ttl={};header={};prefix={}",
+            self.key, self.algorithm, self.claims, self.ttl, self.header, self.prefix
+        )
+    }
+}
+
+impl FromStr for JwtAuth {
+    type Err = String;
+
+    /// Parse JWT settings: `key=KEY[;algorithm=HS256|HS384|HS512|RS256][;claims=JSON]
+    /// [;ttl=SECONDS][;header=NAME][;prefix=PREFIX]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut jwt = JwtAuth::default();
+        for (name, value) in parse_options(s, "JWT")? {
+            match name {
+                "key" => jwt.key = value,
+                "claims" => jwt.claims = value,
+                "header" => jwt.header = value,
+                "prefix" => jwt.prefix = value,
+                "ttl" => {
+                    jwt.ttl = value
+                        .parse()
+                        .map_err(|_| format!("Invalid JWT ttl '{}'. Use seconds", value))?
+                }
+                "algorithm" => {
+                    jwt.algorithm = match value.to_uppercase().as_str() {
+                        "HS256" => JwtAlgorithm::HS256,
+                        "HS384" => JwtAlgorithm::HS384,
+                        "HS512" => JwtAlgorithm::HS512,
+                        "RS256" => JwtAlgorithm::RS256,
+                        _ => {
+                            return Err(format!(
+                                "Invalid JWT algorithm '{}'. Use HS256, HS384, HS512 or RS256",
+                                value
+                            ))
+                        }
+                    }
+                }
+                _ => return Err(format!("Unknown JWT option '{}'", name)),
+            }
+        }
+        if jwt.key.is_empty() {
+            return Err("JWT requires key".to_string());
+        }
+        Ok(jwt)
+    }
+}
+
+/// Split `;`-separated `NAME=VALUE` options
+fn parse_options<'a>(s: &'a str, kind: &str) -> Result<Vec<(&'a str, String)>, String> {
+    s.split(';')
+        .filter(|o| !o.trim().is_empty())
+        .map(|option| {
+            option
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.to_string()))
+                .ok_or_else(|| format!("Invalid {} option '{}'. Use NAME=VALUE", kind, option))
+        })
+        .collect()
+}
+
+impl FromStr for Auth {
+    type Err = String;
+
+    /// Parse an auth setting: `none`, `basic:USER:PASSWORD`, `bearer:TOKEN`,
+    /// `apikey:[header|query:]NAME:VALUE`, `oauth2:OPTIONS` (see [`OAuth2`]),
+    /// `sigv4:ACCESS_KEY:SECRET_KEY:REGION:SERVICE[:SESSION_TOKEN]`,
+    /// `hmac:OPTIONS` (see [`HmacAuth`]), `jwt:OPTIONS` (see [`JwtAuth`]) or
+    /// `digest:USER:PASSWORD`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = s.split_once(':').unwrap_or((s, ""));
+        let invalid = || {
+            format!(
+                "Invalid auth: '{}'. Use none, basic:USER:PASSWORD, bearer:TOKEN, apikey:[header|query:]NAME:VALUE, oauth2:token_url=URL;client_id=ID;..., sigv4:ACCESS_KEY:SECRET_KEY:REGION:SERVICE[:SESSION_TOKEN], hmac:secret=SECRET;..., jwt:key=KEY;... or digest:USER:PASSWORD",
+                s
+            )
+        };
+        match kind.to_lowercase().as_str() {
+            "none" if rest.is_empty() => Ok(Auth::None),
+            "basic" => {
+                let (username, password) = rest.split_once(':').ok_or_else(invalid)?;
+                Ok(Auth::Basic {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                })
+            }
+            "bearer" if !rest.is_empty() => Ok(Auth::Bearer {
+                token: rest.to_string(),
+            }),
+            "apikey" => {
+                let (location, rest) = match rest.split_once(':') {
+                    Some(("header", rest)) => (ApiKeyLocation::Header, rest),
+                    Some(("query", rest)) => (ApiKeyLocation::Query, rest),
+                    _ => (ApiKeyLocation::Header, rest),
+                };
+                let (key, value) = rest.split_once(':').ok_or_else(invalid)?;
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                Ok(Auth::ApiKey {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    location,
+                })
+            }
+            "oauth2" => Ok(Auth::OAuth2(rest.parse()?)),
+            "hmac" => Ok(Auth::Hmac(rest.parse()?)),
+            "jwt" => Ok(Auth::Jwt(rest.parse()?)),
+            "digest" => {
+                let (username, password) = rest.split_once(':').ok_or_else(invalid)?;
+                Ok(Auth::Digest(DigestAuth {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                }))
+            }
+            "sigv4" => match rest.splitn(5, ':').collect::<Vec<_>>()[..] {
+                [access_key, secret_key, region, service, ref session_token @ ..]
+                    if [access_key, secret_key, region, service]
+                        .iter()
+                        .all(|v| !v.is_empty()) =>
+                {
+                    Ok(Auth::AwsSigV4(AwsSigV4 {
+                        access_key: access_key.to_string(),
+                        secret_key: secret_key.to_string(),
+                        session_token: session_token.first().map(|t| t.to_string()),
+                        region: region.to_string(),
+                        service: service.to_string(),
+                    }))
+                }
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl FromStr for FormPart {
+    type Err = String;
+
+    /// Parse a curl style form field: `name=value` or `name=@path;type=mime;filename=x`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid form field: '{}'. Use NAME=VALUE", s))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("Invalid form field: '{}'. Missing name", s));
+        }
+
+        let Some(file) = value.strip_prefix('@') else {
+            return Ok(FormPart::Text {
+                name: name.to_string(),
+                value: value.to_string(),
+            });
+        };
+
+        let mut attrs = file.split(';');
+        let path = attrs.next().unwrap_or_default().trim();
+        if path.is_empty() {
+            return Err(format!("Invalid form field: '{}'. Missing file path", s));
+        }
+
+        let mut content_type = None;
+        let mut filename = None;
+        for attr in attrs {
+            match attr.split_once('=') {
+                Some(("type", v)) => content_type = Some(v.trim().to_string()),
+                Some(("filename", v)) => filename = Some(v.trim().to_string()),
+                _ => return Err(format!("Invalid form field attribute: '{}'", attr)),
+            }
+        }
+
+        Ok(FormPart::File {
+            name: name.to_string(),
+            path: path.to_string(),
+            content_type,
+            filename,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_form_part() {
+        let text: FormPart = "title=hello=world".parse().unwrap();
+        assert_eq!(
+            text,
+            FormPart::Text {
+                name: "title".to_string(),
+                value: "hello=world".to_string()
+            }
+        );
+
+        let file: FormPart = "doc=@/tmp/a.pdf;type=application/pdf;filename=report.pdf"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            file,
+            FormPart::File {
+                name: "doc".to_string(),
+                path: "/tmp/a.pdf".to_string(),
+                content_type: Some("application/pdf".to_string()),
+                filename: Some("report.pdf".to_string()),
+            }
+        );
+        assert_eq!(
+            file.to_string(),
+            "doc=@/tmp/a.pdf;type=application/pdf;filename=report.pdf"
+        );
+
+        assert!("=value".parse::<FormPart>().is_err());
+        assert!("novalue".parse::<FormPart>().is_err());
+        assert!("doc=@".parse::<FormPart>().is_err());
+        assert!("doc=@a.txt;size=3".parse::<FormPart>().is_err());
+    }
+
+    #[test]
+    fn test_parse_auth() {
+        assert_eq!(
+            "basic:alice:pa:ss".parse::<Auth>().unwrap(),
+            Auth::Basic {
+                username: "alice".to_string(),
+                password: "pa:ss".to_string()
+            }
+        );
+        assert_eq!(
+            "apikey:query:api_key:123".parse::<Auth>().unwrap(),
+            Auth::ApiKey {
+                key: "api_key".to_string(),
+                value: "123".to_string(),
+                location: ApiKeyLocation::Query
+            }
+        );
+        let header_key = "apikey:X-Api-Key:123".parse::<Auth>().unwrap();
+        assert_eq!(header_key.to_string(), "apikey:header:X-Api-Key:123");
+        assert_eq!("none".parse::<Auth>().unwrap(), Auth::None);
+        assert!("bearer:".parse::<Auth>().is_err());
+        assert!("unknown:a:b".parse::<Auth>().is_err());
+        assert_eq!(
+            "digest:admin:se:cret".parse::<Auth>().unwrap().to_string(),
+            "digest:admin:se:cret"
+        );
+
+        let oauth = "oauth2:token_url=https://id.example.com/token;client_id=app;client_secret=s;scope=read write"
+            .parse::<Auth>()
+            .unwrap();
+        assert_eq!(
+            oauth.to_string(),
+            "oauth2:grant=client_credentials;token_url=https://id.example.com/token;client_id=app;client_secret=s;scope=read write"
+        );
+        assert!(
+            "oauth2:token_url=https://id.example.com/token;client_id=app;grant=password"
+                .parse::<Auth>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_request_auth_inheritance() {
+        let mut collection = Collection {
+            auth: Some(Auth::Bearer {
+                token: "col".to_string(),
+            }),
+            ..Default::default()
+        };
+        let mut request = Request::default();
+        assert_eq!(collection.request_auth(&request), collection.auth);
+
+        request.auth = Some(Auth::None);
+        assert_eq!(collection.request_auth(&request), None);
+
+        collection.auth = None;
+        request.auth = Some(Auth::Bearer {
+            token: "ep".to_string(),
+        });
+        assert_eq!(collection.request_auth(&request), request.auth);
+    }
+}